@@ -1,5 +1,13 @@
-use bevy::prelude::{Input, KeyCode, Res};
+use crate::actions::gamepad::ActiveGamepad;
+use crate::actions::input_map::InputMap;
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
 
+/// Stick deflection below this is ignored, so a worn stick doesn't drift.
+pub(super) const STICK_DEADZONE: f32 = 0.2;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
 pub enum GameControl {
     Up,
     Down,
@@ -7,31 +15,132 @@ pub enum GameControl {
     Right,
     Grab,
     Interact,
+    Pause,
+}
+
+/// Which inputs a bartender is controlled with.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ControlScheme {
+    /// Every bound key and the active gamepad, for playing alone.
+    All,
+    /// Every bound key.
+    Keyboard,
+    /// One key slot of each control, so two players can share the keyboard.
+    KeySlot(usize),
+    /// The active gamepad.
+    Gamepad,
+}
+
+impl ControlScheme {
+    fn uses_gamepad(&self) -> bool {
+        matches!(self, ControlScheme::All | ControlScheme::Gamepad)
+    }
+}
+
+/// Everything a `GameControl` can be read from.
+#[derive(SystemParam)]
+pub struct ControlInput<'w> {
+    input_map: Res<'w, InputMap>,
+    keyboard: Res<'w, Input<KeyCode>>,
+    gamepad: Res<'w, ActiveGamepad>,
+    gamepad_buttons: Res<'w, Input<GamepadButton>>,
+    gamepad_axes: Res<'w, Axis<GamepadAxis>>,
+}
+
+impl ControlInput<'_> {
+    pub fn pressed(&self, control: GameControl, scheme: ControlScheme) -> bool {
+        self.keyboard.any_pressed(self.keys_for(control, scheme))
+            || self
+                .gamepad_buttons
+                .any_pressed(self.buttons_for(control, scheme))
+    }
+
+    pub fn just_pressed(&self, control: GameControl, scheme: ControlScheme) -> bool {
+        self.keyboard
+            .any_just_pressed(self.keys_for(control, scheme))
+            || self
+                .gamepad_buttons
+                .any_just_pressed(self.buttons_for(control, scheme))
+    }
+
+    /// Left stick position, zero inside the deadzone. Never longer than 1.
+    pub fn stick(&self, scheme: ControlScheme) -> Vec2 {
+        let (Some(gamepad), true) = (self.gamepad.0, scheme.uses_gamepad()) else {
+            return Vec2::ZERO;
+        };
+        let axis = |axis_type| {
+            self.gamepad_axes
+                .get(GamepadAxis::new(gamepad, axis_type))
+                .unwrap_or(0.)
+        };
+        let stick = Vec2::new(
+            axis(GamepadAxisType::LeftStickX),
+            axis(GamepadAxisType::LeftStickY),
+        );
+        if stick.length() < STICK_DEADZONE {
+            Vec2::ZERO
+        } else {
+            stick.clamp_length_max(1.)
+        }
+    }
+
+    fn keys_for(&self, control: GameControl, scheme: ControlScheme) -> Vec<KeyCode> {
+        let keys = self.input_map.keys(control);
+        match scheme {
+            ControlScheme::All | ControlScheme::Keyboard => keys.to_vec(),
+            ControlScheme::KeySlot(slot) => keys.get(slot).copied().into_iter().collect(),
+            ControlScheme::Gamepad => Vec::new(),
+        }
+    }
+
+    fn buttons_for(&self, control: GameControl, scheme: ControlScheme) -> Vec<GamepadButton> {
+        if !scheme.uses_gamepad() {
+            return Vec::new();
+        }
+        self.gamepad
+            .0
+            .iter()
+            .flat_map(|gamepad| {
+                self.input_map
+                    .buttons(control)
+                    .iter()
+                    .map(|button_type| GamepadButton::new(*gamepad, *button_type))
+            })
+            .collect()
+    }
 }
 
 impl GameControl {
-    pub fn pressed(&self, keyboard_input: &Res<Input<KeyCode>>) -> bool {
+    pub const ALL: [GameControl; 7] = [
+        GameControl::Up,
+        GameControl::Down,
+        GameControl::Left,
+        GameControl::Right,
+        GameControl::Grab,
+        GameControl::Interact,
+        GameControl::Pause,
+    ];
+
+    pub fn name(&self) -> &'static str {
         match self {
-            GameControl::Up => {
-                keyboard_input.pressed(KeyCode::W) || keyboard_input.pressed(KeyCode::Up)
-            }
-            GameControl::Down => {
-                keyboard_input.pressed(KeyCode::S) || keyboard_input.pressed(KeyCode::Down)
-            }
-            GameControl::Left => {
-                keyboard_input.pressed(KeyCode::A) || keyboard_input.pressed(KeyCode::Left)
-            }
-            GameControl::Right => {
-                keyboard_input.pressed(KeyCode::D) || keyboard_input.pressed(KeyCode::Right)
-            }
-            GameControl::Grab => keyboard_input.pressed(KeyCode::Space),
-            GameControl::Interact => keyboard_input.pressed(KeyCode::Z),
+            GameControl::Up => "Up",
+            GameControl::Down => "Down",
+            GameControl::Left => "Left",
+            GameControl::Right => "Right",
+            GameControl::Grab => "Grab",
+            GameControl::Interact => "Interact",
+            GameControl::Pause => "Pause",
         }
     }
+
+    /// Just pressed on any input, whoever it belongs to.
+    pub fn just_pressed(&self, input: &ControlInput) -> bool {
+        input.just_pressed(*self, ControlScheme::All)
+    }
 }
 
-pub fn get_movement(control: GameControl, input: &Res<Input<KeyCode>>) -> f32 {
-    if control.pressed(input) {
+pub fn get_movement(control: GameControl, input: &ControlInput, scheme: ControlScheme) -> f32 {
+    if input.pressed(control, scheme) {
         1.0
     } else {
         0.0
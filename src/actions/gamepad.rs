@@ -0,0 +1,111 @@
+use crate::actions::game_control::{ControlScheme, STICK_DEADZONE};
+use crate::actions::InputSource;
+use crate::GameState;
+use bevy::input::gamepad::GamepadConnectionEvent;
+use bevy::prelude::*;
+
+/// The gamepad read for player input, if any are connected.
+#[derive(Default, Resource)]
+pub struct ActiveGamepad(pub Option<Gamepad>);
+
+/// Whether the active gamepad has been played with since it was picked up. Playing alone, the
+/// keyboard and gamepad both work, and only losing the one in use should pause the game.
+#[derive(Default, Resource)]
+pub struct GamepadInUse(bool);
+
+/// Notes when the active gamepad's buttons are pressed or its stick is pushed.
+pub fn note_gamepad_use(
+    active: Res<ActiveGamepad>,
+    buttons: Res<Input<GamepadButton>>,
+    axes: Res<Axis<GamepadAxis>>,
+    mut in_use: ResMut<GamepadInUse>,
+) {
+    let Some(gamepad) = active.0 else {
+        return;
+    };
+    let pressed = buttons
+        .get_pressed()
+        .any(|button| button.gamepad == gamepad);
+    let pushed = [GamepadAxisType::LeftStickX, GamepadAxisType::LeftStickY]
+        .into_iter()
+        .any(|axis_type| {
+            axes.get(GamepadAxis::new(gamepad, axis_type))
+                .is_some_and(|value| value.abs() >= STICK_DEADZONE)
+        });
+    if pressed || pushed {
+        in_use.0 = true;
+    }
+}
+
+/// Picks up gamepads as they are plugged in. If the active one is unplugged, another connected
+/// gamepad takes over. With none left, a running game is paused if a bartender was playing on it.
+pub fn track_gamepads(
+    mut connection_events: EventReader<GamepadConnectionEvent>,
+    gamepads: Res<Gamepads>,
+    mut active: ResMut<ActiveGamepad>,
+    mut in_use: ResMut<GamepadInUse>,
+    source_query: Query<&InputSource>,
+    state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    for event in connection_events.iter() {
+        if event.connected() {
+            if active.0.is_none() {
+                println!("Using {:?}", event.gamepad);
+                active.0 = Some(event.gamepad);
+            }
+        } else if active.0 == Some(event.gamepad) {
+            active.0 = gamepads.iter().next();
+            println!("Lost {:?}, using {:?}", event.gamepad, active.0);
+            let playing_on_it = source_query.iter().any(|source| match source {
+                InputSource::Controls(ControlScheme::Gamepad) => true,
+                InputSource::Controls(ControlScheme::All) => in_use.0,
+                _ => false,
+            });
+            in_use.0 = false;
+            if active.0.is_none() && playing_on_it && state.0 == GameState::Playing {
+                next_state.set(GameState::Paused);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::input::gamepad::GamepadConnection;
+
+    /// Plays with `scheme` on a gamepad that then gets unplugged, and returns whether that paused.
+    fn unplug_while_playing(scheme: ControlScheme, used: bool) -> bool {
+        let gamepad = Gamepad::new(0);
+        let mut app = App::new();
+        app.add_state::<GameState>()
+            .add_event::<GamepadConnectionEvent>()
+            .init_resource::<Gamepads>()
+            .insert_resource(ActiveGamepad(Some(gamepad)))
+            .insert_resource(GamepadInUse(used))
+            .add_system(track_gamepads);
+        app.world.insert_resource(State(GameState::Playing));
+        app.world.spawn(InputSource::Controls(scheme));
+
+        app.world.send_event(GamepadConnectionEvent::new(
+            gamepad,
+            GamepadConnection::Disconnected,
+        ));
+        app.update();
+        assert!(app.world.resource::<ActiveGamepad>().0.is_none());
+        app.world.resource::<NextState<GameState>>().0 == Some(GameState::Paused)
+    }
+
+    #[test]
+    fn losing_a_players_gamepad_pauses() {
+        assert!(unplug_while_playing(ControlScheme::Gamepad, false));
+        assert!(unplug_while_playing(ControlScheme::All, true));
+    }
+
+    #[test]
+    fn losing_an_unused_gamepad_plays_on() {
+        assert!(!unplug_while_playing(ControlScheme::Keyboard, true));
+        assert!(!unplug_while_playing(ControlScheme::All, false));
+    }
+}
@@ -0,0 +1,238 @@
+use crate::actions::GameControl;
+use crate::storage::{self, Location};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+const FILE_NAME: &str = "controls.ron";
+
+/// Keys bound to each control: a primary and an alternate. When two players share the keyboard,
+/// the second plays on the alternates.
+pub const KEY_SLOTS: usize = 2;
+
+/// Keys and gamepad buttons bound to one control.
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct Binding {
+    pub keys: Vec<KeyCode>,
+    pub buttons: Vec<GamepadButtonType>,
+}
+
+/// An input that can be bound to a control.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BoundInput {
+    Key(KeyCode),
+    Button(GamepadButtonType),
+}
+
+/// The bindings of every control, loaded from the config directory at startup.
+#[derive(Resource, Serialize, Deserialize, Clone)]
+pub struct InputMap {
+    bindings: BTreeMap<GameControl, Binding>,
+}
+
+impl Default for InputMap {
+    fn default() -> Self {
+        let binding = |keys: &[KeyCode], buttons: &[GamepadButtonType]| Binding {
+            keys: keys.to_vec(),
+            buttons: buttons.to_vec(),
+        };
+        InputMap {
+            bindings: BTreeMap::from([
+                (
+                    GameControl::Up,
+                    binding(&[KeyCode::W, KeyCode::Up], &[GamepadButtonType::DPadUp]),
+                ),
+                (
+                    GameControl::Down,
+                    binding(&[KeyCode::S, KeyCode::Down], &[GamepadButtonType::DPadDown]),
+                ),
+                (
+                    GameControl::Left,
+                    binding(&[KeyCode::A, KeyCode::Left], &[GamepadButtonType::DPadLeft]),
+                ),
+                (
+                    GameControl::Right,
+                    binding(
+                        &[KeyCode::D, KeyCode::Right],
+                        &[GamepadButtonType::DPadRight],
+                    ),
+                ),
+                (
+                    GameControl::Grab,
+                    binding(
+                        &[KeyCode::Space, KeyCode::RControl],
+                        &[GamepadButtonType::South],
+                    ),
+                ),
+                (
+                    GameControl::Interact,
+                    binding(&[KeyCode::Z, KeyCode::RShift], &[GamepadButtonType::West]),
+                ),
+                (
+                    GameControl::Pause,
+                    binding(&[KeyCode::Escape, KeyCode::P], &[GamepadButtonType::Start]),
+                ),
+            ]),
+        }
+    }
+}
+
+impl InputMap {
+    pub fn keys(&self, control: GameControl) -> &[KeyCode] {
+        self.bindings
+            .get(&control)
+            .map_or(&[], |binding| binding.keys.as_slice())
+    }
+
+    pub fn buttons(&self, control: GameControl) -> &[GamepadButtonType] {
+        self.bindings
+            .get(&control)
+            .map_or(&[], |binding| binding.buttons.as_slice())
+    }
+
+    /// The control an input is bound to, if any.
+    pub fn bound_to(&self, input: BoundInput) -> Option<GameControl> {
+        self.bindings
+            .iter()
+            .find(|(_, binding)| match input {
+                BoundInput::Key(key) => binding.keys.contains(&key),
+                BoundInput::Button(button) => binding.buttons.contains(&button),
+            })
+            .map(|(control, _)| *control)
+    }
+
+    /// Binds a key to a slot of a control, replacing what was there. Binding an empty slot adds
+    /// the key after those already bound. A key already in another slot of the control trades
+    /// places with the one in `slot`. Fails with the control already using the key.
+    pub fn bind_key(
+        &mut self,
+        control: GameControl,
+        slot: usize,
+        key: KeyCode,
+    ) -> Result<(), GameControl> {
+        match self.bound_to(BoundInput::Key(key)) {
+            Some(other) if other != control => return Err(other),
+            _ => {}
+        }
+        let keys = &mut self.bindings.entry(control).or_default().keys;
+        if let Some(bound) = keys.iter().position(|bound| *bound == key) {
+            if slot < keys.len() {
+                keys.swap(bound, slot);
+            } else {
+                let key = keys.remove(bound);
+                keys.push(key);
+            }
+        } else if slot < keys.len() {
+            keys[slot] = key;
+        } else {
+            keys.push(key);
+        }
+        Ok(())
+    }
+
+    /// Binds the gamepad button of a control. Fails with the control already using the button.
+    pub fn bind_button(
+        &mut self,
+        control: GameControl,
+        button: GamepadButtonType,
+    ) -> Result<(), GameControl> {
+        match self.bound_to(BoundInput::Button(button)) {
+            Some(other) if other != control => Err(other),
+            _ => {
+                self.bindings.entry(control).or_default().buttons = vec![button];
+                Ok(())
+            }
+        }
+    }
+
+    pub fn clear_key(&mut self, control: GameControl, slot: usize) {
+        let keys = &mut self.bindings.entry(control).or_default().keys;
+        if slot < keys.len() {
+            keys.remove(slot);
+        }
+    }
+
+    pub fn clear_buttons(&mut self, control: GameControl) {
+        self.bindings.entry(control).or_default().buttons.clear();
+    }
+
+    /// Inputs bound to more than one control, with the controls sharing them.
+    pub fn conflicts(&self) -> Vec<(BoundInput, GameControl, GameControl)> {
+        let inputs = |binding: &Binding| {
+            binding
+                .keys
+                .iter()
+                .map(|key| BoundInput::Key(*key))
+                .chain(
+                    binding
+                        .buttons
+                        .iter()
+                        .map(|button| BoundInput::Button(*button)),
+                )
+                .collect::<Vec<_>>()
+        };
+        let mut conflicts = Vec::new();
+        for (first, (control, binding)) in self.bindings.iter().enumerate() {
+            for input in inputs(binding) {
+                for (other, other_binding) in self.bindings.iter().skip(first + 1) {
+                    if inputs(other_binding).contains(&input) {
+                        conflicts.push((input, *control, *other));
+                    }
+                }
+            }
+        }
+        conflicts
+    }
+
+    /// Reads the bindings from the config file. Missing, corrupt or conflicting bindings give
+    /// the defaults, and controls the file doesn't mention keep their default binding.
+    pub fn load() -> Self {
+        let Some(mut input_map) = storage::load::<InputMap>(Location::Config, FILE_NAME) else {
+            return InputMap::default();
+        };
+        for (control, binding) in InputMap::default().bindings {
+            input_map.bindings.entry(control).or_insert(binding);
+        }
+        let conflicts = input_map.conflicts();
+        if !conflicts.is_empty() {
+            for (input, control, other) in conflicts {
+                println!("{:?} is bound to both {:?} and {:?}", input, control, other);
+            }
+            println!("Using the default controls");
+            return InputMap::default();
+        }
+        input_map
+    }
+
+    pub fn save(&self) {
+        storage::save(Location::Config, FILE_NAME, self);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn binding_a_key_of_another_control_fails() {
+        let mut input_map = InputMap::default();
+        assert_eq!(
+            input_map.bind_key(GameControl::Up, 0, KeyCode::S),
+            Err(GameControl::Down)
+        );
+        assert_eq!(input_map.keys(GameControl::Up), &[KeyCode::W, KeyCode::Up]);
+        assert!(input_map.conflicts().is_empty());
+    }
+
+    #[test]
+    fn binding_a_key_to_another_slot_of_its_control_moves_it() {
+        let mut input_map = InputMap::default();
+        assert_eq!(input_map.bind_key(GameControl::Up, 1, KeyCode::W), Ok(()));
+        assert_eq!(input_map.keys(GameControl::Up), &[KeyCode::Up, KeyCode::W]);
+
+        // Past the bound keys, it goes last.
+        assert_eq!(input_map.bind_key(GameControl::Up, 2, KeyCode::Up), Ok(()));
+        assert_eq!(input_map.keys(GameControl::Up), &[KeyCode::W, KeyCode::Up]);
+        assert!(input_map.conflicts().is_empty());
+    }
+}
@@ -1,49 +1,137 @@
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
 
-use crate::actions::game_control::{get_movement, GameControl};
+use crate::actions::game_control::get_movement;
+pub use crate::actions::game_control::{ControlInput, ControlScheme, GameControl};
+pub use crate::actions::gamepad::ActiveGamepad;
+use crate::actions::gamepad::{note_gamepad_use, track_gamepads, GamepadInUse};
+pub use crate::actions::input_map::{InputMap, KEY_SLOTS};
 use crate::GameState;
 
 mod game_control;
+mod gamepad;
+mod input_map;
 
 pub struct ActionsPlugin;
 
-// This plugin listens for keyboard input and converts the input into Actions
-// Actions can then be used as a resource in other systems to act on the player input.
+// This plugin listens for keyboard and gamepad input and converts the input into Actions
+// Every entity that acts has its own Actions, filled in by its InputSource. Players' come from
+// the inputs of their ControlScheme, NPCs' from the AI.
 impl Plugin for ActionsPlugin {
     fn build(&self, app: &mut App) {
-        app.init_resource::<Actions>()
-            .add_system(set_movement_actions.in_set(OnUpdate(GameState::Playing)));
+        app.init_resource::<PlayMode>()
+            .init_resource::<ActiveGamepad>()
+            .init_resource::<GamepadInUse>()
+            .insert_resource(InputMap::load())
+            .add_system(note_gamepad_use.before(track_gamepads))
+            .add_system(track_gamepads)
+            .add_system(
+                set_movement_actions
+                    .in_set(InputSources)
+                    .in_set(OnUpdate(GameState::Playing)),
+            );
     }
 }
 
-#[derive(Default, Resource)]
-pub struct Actions {
-    pub player_movement: Option<Vec2>,
-    // Current state, last state
-    pub pick_up: (bool, bool),
-    // Current state, last state
-    pub interact: (bool, bool),
-}
-
-pub fn set_movement_actions(mut actions: ResMut<Actions>, keyboard_input: Res<Input<KeyCode>>) {
-    let player_movement = Vec2::new(
-        get_movement(GameControl::Right, &keyboard_input)
-            - get_movement(GameControl::Left, &keyboard_input),
-        get_movement(GameControl::Up, &keyboard_input)
-            - get_movement(GameControl::Down, &keyboard_input),
-    );
-
-    if player_movement != Vec2::ZERO {
-        actions.player_movement = Some(player_movement.normalize());
-    } else {
-        actions.player_movement = None;
+/// How many are playing, chosen in the menu.
+#[derive(Default, Resource, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PlayMode {
+    #[default]
+    Solo,
+    /// Two bartenders, on a gamepad and the keyboard if a gamepad is connected, otherwise
+    /// splitting the keyboard between primary and alternate keys.
+    Coop,
+}
+
+impl PlayMode {
+    /// The control scheme of each bartender.
+    pub fn control_schemes(&self, gamepad: &ActiveGamepad) -> Vec<ControlScheme> {
+        match self {
+            PlayMode::Solo => vec![ControlScheme::All],
+            PlayMode::Coop if gamepad.0.is_some() => {
+                vec![ControlScheme::Keyboard, ControlScheme::Gamepad]
+            }
+            PlayMode::Coop => vec![ControlScheme::KeySlot(0), ControlScheme::KeySlot(1)],
+        }
     }
+}
+
+/// Where an entity's `Actions` come from.
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum InputSource {
+    /// A player, on the inputs of a control scheme.
+    Controls(ControlScheme),
+    /// The NPC AI.
+    Ai,
+    /// A recorded session being played back.
+    Replay,
+}
 
-    let grab = GameControl::Grab.pressed(&keyboard_input);
-    actions.pick_up.1 = actions.pick_up.0;
-    actions.pick_up.0 = grab;
+/// Systems that fill in `Actions` from an input source. Anything acting on `Actions` runs after.
+#[derive(SystemSet, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct InputSources;
 
-    let interact = GameControl::Interact.pressed(&keyboard_input);
-    actions.interact.1 = actions.interact.0;
-    actions.interact.0 = interact;
+/// A button-like action: whether it is held this frame, and whether it was the frame before.
+#[derive(Default, Clone, Copy)]
+pub struct ActionState {
+    pressed: bool,
+    was_pressed: bool,
+}
+
+impl ActionState {
+    /// Sets whether the action is held this frame. Sources call this every frame.
+    pub fn update(&mut self, pressed: bool) {
+        self.was_pressed = self.pressed;
+        self.pressed = pressed;
+    }
+
+    pub fn pressed(&self) -> bool {
+        self.pressed
+    }
+
+    pub fn just_pressed(&self) -> bool {
+        self.pressed && !self.was_pressed
+    }
+}
+
+/// What an entity is trying to do this frame, whoever is controlling it.
+#[derive(Default, Component)]
+pub struct Actions {
+    pub movement: Option<Vec2>,
+    pub pick_up: ActionState,
+    pub interact: ActionState,
+}
+
+pub fn set_movement_actions(
+    mut actions_query: Query<(&mut Actions, &InputSource)>,
+    input: ControlInput,
+) {
+    for (mut actions, source) in &mut actions_query {
+        let InputSource::Controls(scheme) = *source else {
+            continue;
+        };
+        let player_movement = Vec2::new(
+            get_movement(GameControl::Right, &input, scheme)
+                - get_movement(GameControl::Left, &input, scheme),
+            get_movement(GameControl::Up, &input, scheme)
+                - get_movement(GameControl::Down, &input, scheme),
+        );
+        let stick = input.stick(scheme);
+
+        if player_movement != Vec2::ZERO {
+            actions.movement = Some(player_movement.normalize());
+        } else if stick != Vec2::ZERO {
+            // Keep the stick's magnitude, so a light push walks slower.
+            actions.movement = Some(stick);
+        } else {
+            actions.movement = None;
+        }
+
+        actions
+            .pick_up
+            .update(input.pressed(GameControl::Grab, scheme));
+        actions
+            .interact
+            .update(input.pressed(GameControl::Interact, scheme));
+    }
 }
@@ -1,44 +1,322 @@
+use crate::player::PlayerHeading;
+use crate::sheet::SpriteSheet;
 use crate::GameState;
 use bevy::prelude::*;
+use serde::Deserialize;
 
 pub struct AnimatePlugin;
 
 impl Plugin for AnimatePlugin {
     fn build(&self, app: &mut App) {
-        app.add_system(animate_sprites.in_set(OnUpdate(GameState::Playing)));
+        app.add_event::<AnimationFinished>().add_systems(
+            (update_animation_states, animate_sprites)
+                .chain()
+                .in_set(Animating)
+                .in_set(OnUpdate(GameState::Playing)),
+        );
     }
 }
 
-#[derive(Clone)]
+/// Picks and plays the frames of each sprite. Whatever decides what characters are doing runs
+/// before this, so the change shows the same frame.
+#[derive(SystemSet, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Animating;
+
+/// Sent when an animation that doesn't loop has shown its last frame for its full duration.
+pub struct AnimationFinished {
+    pub entity: Entity,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Deserialize)]
+pub enum Playback {
+    /// Through the frames this many times, then holding the last one.
+    Times(u32),
+    /// Through the frames, back to the first, forever.
+    Loop,
+    /// Forwards through the frames then backwards, forever.
+    PingPong,
+}
+
+impl Default for Playback {
+    fn default() -> Self {
+        Playback::Times(1)
+    }
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(try_from = "Clip")]
 pub struct Animation {
-    frames: Vec<usize>,
-    timing: f32,
-    looping: bool,
+    /// Sprite index and seconds to show it, for each frame.
+    frames: Vec<(usize, f32)>,
+    playback: Playback,
 }
 
 impl Animation {
-    pub fn new(frames: &[usize], timing: f32, looping: bool) -> Self {
+    fn timed(frames: &[(usize, f32)], playback: Playback) -> Self {
+        assert!(!frames.is_empty(), "An animation needs a frame");
         Animation {
             frames: frames.to_vec(),
-            timing,
-            looping,
+            playback,
+        }
+    }
+
+    fn evenly(frames: &[usize], timing: f32) -> Vec<(usize, f32)> {
+        frames.iter().map(|index| (*index, timing)).collect()
+    }
+}
+
+/// An animation as written in a sheet file.
+#[derive(Deserialize)]
+struct Clip {
+    frames: Vec<usize>,
+    /// Seconds each frame is shown, unless they're given one by one in `durations`.
+    #[serde(default)]
+    timing: f32,
+    #[serde(default)]
+    durations: Vec<f32>,
+    #[serde(default)]
+    playback: Playback,
+}
+
+impl TryFrom<Clip> for Animation {
+    type Error = String;
+
+    fn try_from(clip: Clip) -> Result<Self, Self::Error> {
+        if clip.frames.is_empty() {
+            return Err("A clip needs a frame".to_string());
+        }
+        let frames = if clip.durations.is_empty() {
+            Self::evenly(&clip.frames, clip.timing)
+        } else if clip.durations.len() == clip.frames.len() {
+            clip.frames.into_iter().zip(clip.durations).collect()
+        } else {
+            return Err(format!(
+                "A clip has {} frames but {} durations",
+                clip.frames.len(),
+                clip.durations.len()
+            ));
+        };
+        // Frames that take no time would never let a looping clip move on. Leaving out both
+        // `timing` and `durations` is one way to end up with them.
+        if let Some((_, duration)) = frames.iter().find(|(_, duration)| *duration <= 0.) {
+            return Err(format!("A clip has a frame lasting {} seconds", duration));
         }
+        Ok(Animation::timed(&frames, clip.playback))
     }
 }
 
 struct RunningAnimation {
     animation: Animation,
     frame_idx: usize,
-    timer: Timer,
+    /// Seconds the current frame has been shown.
+    elapsed: f32,
+    /// Times through the frames so far.
+    passes: u32,
+    /// Going back towards the first frame, when ping-ponging.
+    reversed: bool,
+    finished: bool,
 }
 
 impl RunningAnimation {
     fn new(animation: &Animation) -> Self {
-        let timer = Timer::from_seconds(animation.timing, TimerMode::Once);
         RunningAnimation {
             animation: animation.clone(),
             frame_idx: 0,
-            timer,
+            elapsed: 0.,
+            passes: 0,
+            reversed: false,
+            finished: false,
+        }
+    }
+
+    fn sprite_index(&self) -> usize {
+        self.animation.frames[self.frame_idx].0
+    }
+
+    /// Moves on by `delta` seconds, as many frames as that takes. Returns true on finishing.
+    fn tick(&mut self, delta: f32) -> bool {
+        if self.finished {
+            return false;
+        }
+        self.elapsed += delta;
+        loop {
+            let duration = self.animation.frames[self.frame_idx].1;
+            if self.elapsed < duration {
+                return false;
+            }
+            self.elapsed -= duration;
+            if !self.advance() {
+                self.finished = true;
+                return true;
+            }
+        }
+    }
+
+    /// Steps to the next frame. Returns false if there isn't one.
+    fn advance(&mut self) -> bool {
+        let last = self.animation.frames.len() - 1;
+        match self.animation.playback {
+            Playback::Times(times) => {
+                if self.frame_idx < last {
+                    self.frame_idx += 1;
+                } else {
+                    self.passes += 1;
+                    if self.passes >= times {
+                        return false;
+                    }
+                    self.frame_idx = 0;
+                }
+            }
+            Playback::Loop => {
+                self.frame_idx = if self.frame_idx < last {
+                    self.frame_idx + 1
+                } else {
+                    0
+                };
+            }
+            Playback::PingPong => {
+                if last == 0 {
+                    return true;
+                }
+                if self.reversed && self.frame_idx == 0 {
+                    self.reversed = false;
+                } else if !self.reversed && self.frame_idx == last {
+                    self.reversed = true;
+                }
+                if self.reversed {
+                    self.frame_idx -= 1;
+                } else {
+                    self.frame_idx += 1;
+                }
+            }
+        }
+        true
+    }
+}
+
+/// Something a character plays out on the spot, like a dance.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Performance {
+    Drink,
+    Talk,
+    Fight,
+    Dance,
+    Cry,
+    Puke,
+}
+
+/// What a character's animation shows. Each is a clip, or one of a few, looked up by name in the
+/// character's sheet.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AnimationState {
+    Idle(PlayerHeading),
+    Walk(PlayerHeading),
+    /// Standing with something in hand.
+    Carry(PlayerHeading),
+    CarryWalk(PlayerHeading),
+    Perform(Performance, PlayerHeading),
+}
+
+impl AnimationState {
+    /// Names of the clips to play, the first one the sheet has. Characters without a walk or
+    /// carry clip fall back to standing still.
+    fn clips(&self) -> Vec<String> {
+        match self {
+            AnimationState::Idle(heading) => vec![format!("idle_{}", heading.name())],
+            AnimationState::Walk(heading) => vec![
+                format!("walk_{}", heading.name()),
+                format!("idle_{}", heading.name()),
+            ],
+            AnimationState::Carry(heading) => vec![
+                format!("carry_{}", heading.name()),
+                format!("idle_{}", heading.name()),
+            ],
+            AnimationState::CarryWalk(heading) => vec![
+                format!("carry_walk_{}", heading.name()),
+                format!("walk_{}", heading.name()),
+                format!("carry_{}", heading.name()),
+                format!("idle_{}", heading.name()),
+            ],
+            AnimationState::Perform(performance, heading) => {
+                // Talking and fighting are drawn turned to one side.
+                let side = match heading {
+                    PlayerHeading::Left => "left",
+                    _ => "right",
+                };
+                let clip = match performance {
+                    Performance::Drink => "drink".to_string(),
+                    Performance::Talk => format!("talk_{}", side),
+                    Performance::Fight => format!("punch_{}", side),
+                    Performance::Dance => "dance".to_string(),
+                    Performance::Cry => "cry".to_string(),
+                    Performance::Puke => "puke".to_string(),
+                };
+                vec![clip]
+            }
+        }
+    }
+}
+
+/// Chooses a character's animation from how they're moving, whether they're carrying anything,
+/// and what they're performing, then plays it through their `AnimationComponent`.
+#[derive(Component, Default)]
+pub struct AnimationStateMachine {
+    heading: PlayerHeading,
+    /// Pixels a second.
+    speed: f32,
+    carrying: bool,
+    performing: Option<Performance>,
+    /// The state being shown, once there is one.
+    state: Option<AnimationState>,
+}
+
+impl AnimationStateMachine {
+    /// Moving at `speed` pixels a second, or standing still at zero. Walking off ends a
+    /// performance.
+    pub fn set_movement(&mut self, heading: PlayerHeading, speed: f32) {
+        self.heading = heading;
+        self.speed = speed;
+        if self.moving() {
+            self.performing = None;
+        }
+    }
+
+    fn moving(&self) -> bool {
+        self.speed > 0.
+    }
+
+    pub fn set_carrying(&mut self, carrying: bool) {
+        self.carrying = carrying;
+    }
+
+    /// Plays out `performance` until its animation finishes.
+    pub fn perform(&mut self, performance: Performance) {
+        self.performing = Some(performance);
+    }
+
+    pub fn is_performing(&self) -> bool {
+        self.performing.is_some()
+    }
+
+    fn next_state(&self) -> AnimationState {
+        let heading = self.heading;
+        match (self.performing, self.moving(), self.carrying) {
+            (Some(performance), _, _) => AnimationState::Perform(performance, heading),
+            (None, false, false) => AnimationState::Idle(heading),
+            (None, true, false) => AnimationState::Walk(heading),
+            (None, false, true) => AnimationState::Carry(heading),
+            (None, true, true) => AnimationState::CarryWalk(heading),
+        }
+    }
+
+    /// How fast to play the animation for `state`. Walks keep pace with the feet, so they play
+    /// at the speed they're timed for in the sheet and faster or slower from there.
+    fn rate(&self, state: AnimationState, walk_speed: f32) -> f32 {
+        match state {
+            AnimationState::Walk(_) | AnimationState::CarryWalk(_) if walk_speed > 0. => {
+                self.speed / walk_speed
+            }
+            _ => 1.,
         }
     }
 }
@@ -46,11 +324,16 @@ impl RunningAnimation {
 #[derive(Component)]
 pub struct AnimationComponent {
     running: Option<RunningAnimation>,
+    /// How fast to play, 1 being as timed.
+    rate: f32,
 }
 
 impl Default for AnimationComponent {
     fn default() -> Self {
-        AnimationComponent { running: None }
+        AnimationComponent {
+            running: None,
+            rate: 1.,
+        }
     }
 }
 
@@ -59,28 +342,219 @@ impl AnimationComponent {
         self.running = Some(RunningAnimation::new(animation));
     }
 
-    pub fn stop_animation(&mut self) {
-        self.running = None;
+    /// Whether the last animation started has played out. Looping ones never do.
+    pub fn finished(&self) -> bool {
+        !matches!(&self.running, Some(running) if !running.finished)
+    }
+}
+
+fn update_animation_states(
+    sheets: Res<Assets<SpriteSheet>>,
+    mut finished: EventReader<AnimationFinished>,
+    mut query: Query<(
+        &mut AnimationStateMachine,
+        &mut AnimationComponent,
+        &Handle<SpriteSheet>,
+    )>,
+) {
+    // A performance is over when its animation is. Anything else finishing doesn't end it.
+    for event in finished.iter() {
+        let Ok((mut machine, animation, _)) = query.get_mut(event.entity) else {
+            continue;
+        };
+        if let Some(AnimationState::Perform(..)) = machine.state {
+            if animation.finished() {
+                machine.performing = None;
+            }
+        }
+    }
+
+    for (mut machine, mut animation, sheet) in &mut query {
+        let Some(sheet) = sheets.get(sheet) else {
+            continue;
+        };
+
+        let next = machine.next_state();
+        let rate = machine.rate(next, sheet.walk_speed);
+        if animation.rate != rate {
+            animation.rate = rate;
+        }
+        if Some(next) == machine.state {
+            continue;
+        }
+
+        let clips = next.clips();
+        let Some(clip) = clips.iter().find_map(|name| sheet.clip(name)) else {
+            println!("No {} animation", clips[0]);
+            // Nothing to wait for, so a performance is over straight away.
+            machine.performing = None;
+            machine.state = Some(next);
+            continue;
+        };
+        animation.start_animation(clip);
+        machine.state = Some(next);
     }
 }
 
 fn animate_sprites(
     time: Res<Time>,
-    mut query: Query<(&mut TextureAtlasSprite, &mut AnimationComponent)>,
+    mut finished: EventWriter<AnimationFinished>,
+    mut query: Query<(Entity, &mut TextureAtlasSprite, &mut AnimationComponent)>,
 ) {
-    for (mut sprite, mut animation_component) in &mut query {
+    for (entity, mut sprite, mut animation_component) in &mut query {
+        let rate = animation_component.rate;
         let Some(running) = &mut animation_component.running else {
             continue;
         };
 
-        sprite.index = running.animation.frames[running.frame_idx];
-        running.timer.tick(time.delta());
-        if running.timer.just_finished() {
-            running.frame_idx += 1;
-            if running.frame_idx >= running.animation.frames.len() {
-                running.frame_idx = 0;
-            }
-            running.timer.reset();
+        if running.tick(time.delta_seconds() * rate) {
+            finished.send(AnimationFinished { entity });
         }
+        let index = running.sprite_index();
+        if sprite.index != index {
+            sprite.index = index;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clip(frames: &[usize], timing: f32, playback: Playback) -> Animation {
+        Animation::timed(&Animation::evenly(frames, timing), playback)
+    }
+
+    /// The sprite indices shown over `ticks` of `delta` seconds, and whether each finished.
+    fn play(animation: &Animation, delta: f32, ticks: usize) -> Vec<(usize, bool)> {
+        let mut running = RunningAnimation::new(animation);
+        (0..ticks)
+            .map(|_| {
+                let finished = running.tick(delta);
+                (running.sprite_index(), finished)
+            })
+            .collect()
+    }
+
+    fn indices(played: &[(usize, bool)]) -> Vec<usize> {
+        played.iter().map(|(index, _)| *index).collect()
+    }
+
+    #[test]
+    fn looping_wraps_to_the_first_frame() {
+        let played = play(&clip(&[1, 2, 3], 1., Playback::Loop), 1., 5);
+        assert_eq!(indices(&played), [2, 3, 1, 2, 3]);
+        assert!(played.iter().all(|(_, finished)| !finished));
+    }
+
+    #[test]
+    fn one_shots_hold_the_last_frame_and_finish_once() {
+        let played = play(&clip(&[1, 2, 3], 1., Playback::Times(1)), 1., 5);
+        assert_eq!(indices(&played), [2, 3, 3, 3, 3]);
+        let finished: Vec<bool> = played.iter().map(|(_, finished)| *finished).collect();
+        assert_eq!(finished, [false, false, true, false, false]);
+    }
+
+    #[test]
+    fn first_frame_is_shown_for_its_duration() {
+        let played = play(&clip(&[1, 2], 1., Playback::Loop), 0.25, 5);
+        assert_eq!(indices(&played), [1, 1, 1, 2, 2]);
+    }
+
+    #[test]
+    fn repeats_finish_after_their_passes() {
+        let played = play(&clip(&[1, 2], 0.5, Playback::Times(3)), 0.5, 7);
+        assert_eq!(indices(&played), [2, 1, 2, 1, 2, 2, 2]);
+        assert!(played[5].1);
+    }
+
+    #[test]
+    fn ping_pong_turns_at_each_end() {
+        let played = play(&clip(&[1, 2, 3], 1., Playback::PingPong), 1., 7);
+        assert_eq!(indices(&played), [2, 3, 2, 1, 2, 3, 2]);
+    }
+
+    #[test]
+    fn frames_have_their_own_durations() {
+        let animation = Animation::timed(&[(1, 0.5), (2, 2.)], Playback::Times(1));
+        let played = play(&animation, 0.5, 5);
+        assert_eq!(indices(&played), [2, 2, 2, 2, 2]);
+        assert!(played[4].1);
+    }
+
+    #[test]
+    fn clips_are_read_from_sheet_files() {
+        let clip: Animation =
+            ron::from_str("(frames: [4, 5], timing: 0.5, playback: Times(2))").unwrap();
+        assert_eq!(indices(&play(&clip, 0.5, 4)), [5, 4, 5, 5]);
+
+        let clip: Animation = ron::from_str("(frames: [1, 2], durations: [0.5, 1.0])").unwrap();
+        assert_eq!(clip.playback, Playback::Times(1));
+        assert_eq!(clip.frames, [(1, 0.5), (2, 1.)]);
+
+        assert!(ron::from_str::<Animation>("(frames: [1, 2], durations: [0.5])").is_err());
+        assert!(ron::from_str::<Animation>("(frames: [])").is_err());
+    }
+
+    #[test]
+    fn clips_without_time_to_show_frames_are_rejected() {
+        assert!(ron::from_str::<Animation>("(frames: [1, 2], playback: Loop)").is_err());
+        assert!(ron::from_str::<Animation>("(frames: [1, 2], timing: 0.0)").is_err());
+        assert!(ron::from_str::<Animation>("(frames: [1], timing: -1.0)").is_err());
+        assert!(ron::from_str::<Animation>(
+            "(frames: [1, 2], durations: [0.5, 0.0], playback: PingPong)"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn states_follow_movement_carrying_and_performances() {
+        let mut machine = AnimationStateMachine::default();
+        assert_eq!(
+            machine.next_state(),
+            AnimationState::Idle(PlayerHeading::Down)
+        );
+
+        machine.set_movement(PlayerHeading::Left, 150.);
+        assert_eq!(
+            machine.next_state(),
+            AnimationState::Walk(PlayerHeading::Left)
+        );
+        machine.set_carrying(true);
+        assert_eq!(
+            machine.next_state(),
+            AnimationState::CarryWalk(PlayerHeading::Left)
+        );
+        machine.set_movement(PlayerHeading::Left, 0.);
+        assert_eq!(
+            machine.next_state(),
+            AnimationState::Carry(PlayerHeading::Left)
+        );
+
+        machine.perform(Performance::Talk);
+        let talking = machine.next_state();
+        assert_eq!(talking.clips(), ["talk_left"]);
+        machine.set_movement(PlayerHeading::Up, 75.);
+        assert!(!machine.is_performing());
+    }
+
+    #[test]
+    fn walks_play_at_the_speed_of_the_feet() {
+        let mut machine = AnimationStateMachine::default();
+        machine.set_movement(PlayerHeading::Right, 75.);
+        assert_eq!(machine.rate(machine.next_state(), 150.), 0.5);
+        machine.set_carrying(true);
+        assert_eq!(machine.rate(machine.next_state(), 150.), 0.5);
+
+        machine.set_movement(PlayerHeading::Right, 0.);
+        assert_eq!(machine.rate(machine.next_state(), 150.), 1.);
+        machine.perform(Performance::Drink);
+        assert_eq!(machine.rate(machine.next_state(), 150.), 1.);
+    }
+
+    #[test]
+    fn long_frames_skip_ahead() {
+        let played = play(&clip(&[1, 2, 3, 4], 0.25, Playback::Loop), 0.625, 2);
+        assert_eq!(indices(&played), [3, 2]);
     }
 }
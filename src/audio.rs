@@ -1,6 +1,6 @@
 use crate::actions::{set_movement_actions, Actions};
 use crate::loading::AudioAssets;
-use crate::GameState;
+use crate::{starting_session, GameState};
 use bevy::prelude::*;
 use bevy_kira_audio::prelude::*;
 
@@ -11,7 +11,20 @@ impl Plugin for InternalAudioPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugin(AudioPlugin)
             .init_resource::<SoundStates>()
-            .add_system(start_audio.in_schedule(OnEnter(GameState::Playing)))
+            .add_system(
+                start_audio
+                    .run_if(starting_session)
+                    .in_schedule(OnEnter(GameState::Playing)),
+            )
+            .add_system(pause_audio.in_schedule(OnEnter(GameState::Paused)))
+            .add_system(pause_audio.in_schedule(OnEnter(GameState::Results)))
+            .add_system(stop_audio.in_schedule(OnEnter(GameState::Menu)))
+            .add_system(
+                stop_audio
+                    .run_if(starting_session)
+                    .in_schedule(OnEnter(GameState::Playing))
+                    .before(start_audio),
+            )
             .add_system(
                 control_flying_sound
                     .after(set_movement_actions)
@@ -66,6 +79,36 @@ fn start_audio(mut commands: Commands, audio_assets: Res<AudioAssets>, audio: Re
     commands.insert_resource(ChattingAudio(handle));
 }
 
+// Stop the looping sounds of the last session, if there was one.
+fn stop_audio(
+    mut commands: Commands,
+    background: Option<Res<BackgroundAudio>>,
+    drinking: Option<Res<DrinkingAudio>>,
+    chatting: Option<Res<ChattingAudio>>,
+    mut audio_instances: ResMut<Assets<AudioInstance>>,
+) {
+    let handles = [
+        background.map(|audio| audio.0.clone()),
+        drinking.map(|audio| audio.0.clone()),
+        chatting.map(|audio| audio.0.clone()),
+    ];
+    for handle in handles.into_iter().flatten() {
+        if let Some(instance) = audio_instances.get_mut(&handle) {
+            instance.stop(AudioTween::default());
+        }
+    }
+    commands.remove_resource::<BackgroundAudio>();
+    commands.remove_resource::<DrinkingAudio>();
+    commands.remove_resource::<ChattingAudio>();
+    commands.insert_resource(SoundStates::default());
+}
+
+// Everything is paused here, `control_flying_sound` resumes what should be playing once the game
+// is back in `GameState::Playing`.
+fn pause_audio(audio: Res<Audio>) {
+    audio.pause();
+}
+
 fn control_flying_sound(
     sound_states: Res<SoundStates>,
     audio: Res<DrinkingAudio>,
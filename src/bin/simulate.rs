@@ -0,0 +1,22 @@
+// Plays shifts without a window and prints aggregate stats, for tuning the customers:
+//
+//     cargo run --release --bin simulate -- --minutes 6000 --serve-after 10 --seed 1
+//
+// See `SimulationSettings::from_args` for the options.
+
+use bevy::asset::AssetPlugin;
+use bevy::hierarchy::HierarchyPlugin;
+use bevy::prelude::*;
+use bevy::transform::TransformPlugin;
+use lunas_libations::{SimulationPlugin, SimulationSettings};
+
+fn main() {
+    App::new()
+        .insert_resource(SimulationSettings::from_args())
+        .add_plugins(MinimalPlugins)
+        .add_plugin(AssetPlugin::default())
+        .add_plugin(HierarchyPlugin)
+        .add_plugin(TransformPlugin)
+        .add_plugin(SimulationPlugin)
+        .run();
+}
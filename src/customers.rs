@@ -0,0 +1,122 @@
+use crate::level::Level;
+use crate::loading::{LevelAssets, SheetAssets};
+use crate::npc::NPC;
+use crate::player::spawn_npc;
+use crate::rng::GameRng;
+use crate::sheet::SpriteSheet;
+use crate::shift::Shift;
+use crate::tilemap::TileMap;
+use crate::world::Tile;
+use crate::{starting_session, GameState};
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use rand::prelude::*;
+
+pub struct CustomersPlugin;
+
+/// This plugin brings customers in through the entrance over the course of a shift, following the
+/// arrival curve of the level.
+impl Plugin for CustomersPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(
+            start_arrivals
+                .run_if(starting_session)
+                .in_schedule(OnEnter(GameState::Playing)),
+        )
+        .add_system(customer_arrivals.in_set(OnUpdate(GameState::Playing)));
+    }
+}
+
+#[derive(Resource)]
+pub struct CustomerArrivals {
+    /// Customers due to arrive. One comes in each time this reaches 1.
+    due: f32,
+    /// Set once we have said the level has no entrance, so it is only said once.
+    no_entrance: bool,
+}
+
+fn start_arrivals(mut commands: Commands) {
+    // Open the doors with someone already waiting.
+    commands.insert_resource(CustomerArrivals {
+        due: 1.,
+        no_entrance: false,
+    });
+}
+
+/// How customers get into the bar, and how many already have.
+#[derive(SystemParam)]
+struct Door<'w, 's> {
+    commands: Commands<'w, 's>,
+    sheet_assets: Res<'w, SheetAssets>,
+    sheets: Res<'w, Assets<SpriteSheet>>,
+    tile_map_query: Query<'w, 's, (&'static TileMap, &'static Transform)>,
+    tile_query: Query<'w, 's, &'static Tile>,
+    npc_query: Query<'w, 's, (), With<NPC>>,
+}
+
+impl Door<'_, '_> {
+    fn customers(&self) -> usize {
+        self.npc_query.iter().count()
+    }
+
+    /// Brings a customer in through one of the entrances. False if the level has none.
+    fn admit(&mut self, rng: &mut impl Rng) -> bool {
+        let (tile_map, tile_map_transform) = self.tile_map_query.single();
+        let entrances: Vec<IVec2> = tile_map
+            .iter()
+            .filter(|(_, entity)| matches!(self.tile_query.get(*entity), Ok(Tile::Entrance)))
+            .map(|(point, _)| point)
+            .collect();
+        let Some(entrance) = entrances.choose(rng) else {
+            return false;
+        };
+
+        let position = tile_map.tile_to_world(tile_map_transform.translation, *entrance);
+        spawn_npc(
+            &mut self.commands,
+            &self.sheet_assets,
+            &self.sheets,
+            position,
+            rng,
+        );
+        true
+    }
+}
+
+fn customer_arrivals(
+    time: Res<Time>,
+    level_assets: Res<LevelAssets>,
+    levels: Res<Assets<Level>>,
+    shift: Res<Shift>,
+    mut rng: ResMut<GameRng>,
+    mut arrivals: ResMut<CustomerArrivals>,
+    mut door: Door,
+) {
+    let Some(level) = levels.get(&level_assets.bar) else {
+        return;
+    };
+
+    let delta = time.delta_seconds();
+    arrivals.due += level.shift.arrival_rate(shift.elapsed()) * delta;
+    if arrivals.due < 1. {
+        return;
+    }
+
+    if door.customers() >= level.shift.capacity {
+        // Full up, the next customer waits outside until someone leaves.
+        arrivals.due = 1.;
+        return;
+    }
+
+    if !door.admit(&mut *rng) {
+        // No way in, so nobody queues up outside either.
+        if !arrivals.no_entrance {
+            println!("Level has no entrance, customers can't arrive");
+            arrivals.no_entrance = true;
+        }
+        arrivals.due = 1.;
+        return;
+    }
+    println!("Customer arrived");
+    arrivals.due -= 1.;
+}
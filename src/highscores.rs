@@ -0,0 +1,101 @@
+use crate::level::Level;
+use crate::loading::LevelAssets;
+use crate::score::Score;
+use crate::storage::{self, Location};
+use crate::GameState;
+use bevy::prelude::*;
+use chrono::{Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+
+/// Number of results kept in the table.
+const TABLE_SIZE: usize = 10;
+const FILE_NAME: &str = "highscores.ron";
+
+pub struct HighScoresPlugin;
+
+/// This plugin keeps the best shift results in a file under the user's data directory.
+impl Plugin for HighScoresPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(load_high_scores)
+            .add_system(record_high_score.in_schedule(OnEnter(GameState::Results)));
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct HighScore {
+    pub score: i32,
+    pub date: NaiveDate,
+    pub level: String,
+    pub drinks_served: usize,
+}
+
+/// The best results, highest score first.
+#[derive(Resource, Serialize, Deserialize, Default)]
+pub struct HighScores {
+    entries: Vec<HighScore>,
+}
+
+impl HighScores {
+    pub fn entries(&self) -> &[HighScore] {
+        &self.entries
+    }
+
+    /// Adds a result to the table, returning its rank if it made it in.
+    pub fn insert(&mut self, entry: HighScore) -> Option<usize> {
+        // Ties go below the results already in the table.
+        let rank = self
+            .entries
+            .iter()
+            .position(|existing| existing.score < entry.score)
+            .unwrap_or(self.entries.len());
+        if rank >= TABLE_SIZE {
+            return None;
+        }
+        self.entries.insert(rank, entry);
+        self.entries.truncate(TABLE_SIZE);
+        Some(rank)
+    }
+
+    /// Reads the table from disk. A missing or corrupt file gives a fresh table.
+    fn load() -> Self {
+        let Some(mut high_scores) = storage::load::<HighScores>(Location::Data, FILE_NAME) else {
+            return HighScores::default();
+        };
+        high_scores
+            .entries
+            .sort_by_key(|entry| Reverse(entry.score));
+        high_scores.entries.truncate(TABLE_SIZE);
+        high_scores
+    }
+
+    fn save(&self) {
+        storage::save(Location::Data, FILE_NAME, self);
+    }
+}
+
+fn load_high_scores(mut commands: Commands) {
+    commands.insert_resource(HighScores::load());
+}
+
+fn record_high_score(
+    score: Res<Score>,
+    level_assets: Res<LevelAssets>,
+    levels: Res<Assets<Level>>,
+    mut high_scores: ResMut<HighScores>,
+) {
+    let level = levels
+        .get(&level_assets.bar)
+        .map(|level| level.name.clone())
+        .unwrap_or_default();
+    let entry = HighScore {
+        score: score.total(),
+        date: Local::now().date_naive(),
+        level,
+        drinks_served: score.drinks_served(),
+    };
+    if let Some(rank) = high_scores.insert(entry) {
+        println!("New high score, rank {}", rank + 1);
+        high_scores.save();
+    }
+}
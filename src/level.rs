@@ -0,0 +1,173 @@
+use crate::player::{Container, Interactable, Item, Mixer};
+use crate::world::{Tile, WORLD_SIZE};
+use bevy::asset::{AssetLoader, LoadContext, LoadedAsset};
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use bevy::utils::{BoxedFuture, HashMap};
+use serde::Deserialize;
+
+/// A bar layout, loaded from a `.level.ron` file.
+///
+/// The layout is a plain text grid, listed top row first, so the last row is tile y 0. Each
+/// character is looked up in the legend to find the tile (and anything placed on it). A space
+/// leaves the tile empty.
+#[derive(Deserialize, TypeUuid)]
+#[uuid = "8d1c3a5e-5f0b-4d4e-9a57-3f0f2b1c6e42"]
+pub struct Level {
+    /// Shown in the high-score table.
+    pub name: String,
+    #[serde(default)]
+    pub shift: ShiftConfig,
+    pub rows: Vec<String>,
+    pub legend: HashMap<char, LegendEntry>,
+}
+
+#[derive(Deserialize)]
+pub struct LegendEntry {
+    pub tile: Tile,
+    #[serde(default)]
+    pub interactable: Option<Placement>,
+    #[serde(default)]
+    pub spawn: Option<SpawnPoint>,
+}
+
+/// Interactables that can be placed on a tile.
+#[derive(Deserialize, Clone)]
+pub enum Placement {
+    Spawner(Item),
+    Mixer,
+    Container,
+    Trash,
+}
+
+impl Placement {
+    pub fn interactable(&self) -> Interactable {
+        match self {
+            Placement::Spawner(item) => Interactable::Spawner(item.clone()),
+            Placement::Mixer => Interactable::Mixer(Mixer::new()),
+            Placement::Container => Interactable::Container(Container::new()),
+            Placement::Trash => Interactable::Trash,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum SpawnPoint {
+    Player,
+}
+
+/// How customers arrive over a shift.
+#[derive(Deserialize, Clone)]
+pub struct ShiftConfig {
+    /// Length of a shift, in seconds.
+    pub length: f32,
+    /// Most customers in the bar at once.
+    pub capacity: usize,
+    /// Customers arriving per minute, as `(fraction of shift, rate)` points in shift order.
+    pub arrivals: Vec<(f32, f32)>,
+}
+
+impl Default for ShiftConfig {
+    fn default() -> Self {
+        ShiftConfig {
+            length: 300.,
+            capacity: 8,
+            arrivals: vec![(0., 4.), (1., 4.)],
+        }
+    }
+}
+
+impl ShiftConfig {
+    /// Customers arriving per second at `elapsed` seconds into the shift.
+    pub fn arrival_rate(&self, elapsed: f32) -> f32 {
+        if elapsed >= self.length {
+            return 0.;
+        }
+        let progress = elapsed / self.length;
+        let mut rate = self.arrivals.first().map_or(0., |(_, rate)| *rate);
+        for window in self.arrivals.windows(2) {
+            let ((start, from), (end, to)) = (window[0], window[1]);
+            if progress < start {
+                break;
+            }
+            if progress <= end && end > start {
+                rate = from + (to - from) * (progress - start) / (end - start);
+                break;
+            }
+            rate = to;
+        }
+        rate / 60.
+    }
+}
+
+impl Level {
+    /// Iterate all non-empty tiles in the layout, with their tile index.
+    pub fn tiles(&self) -> impl Iterator<Item = (IVec2, &LegendEntry)> {
+        let height = self.rows.len() as i32;
+        self.rows.iter().enumerate().flat_map(move |(row, line)| {
+            let y = height - 1 - row as i32;
+            line.chars().enumerate().filter_map(move |(x, c)| {
+                if c == ' ' {
+                    return None;
+                }
+                let entry = self.legend.get(&c)?;
+                Some((IVec2::new(x as i32, y), entry))
+            })
+        })
+    }
+
+    /// Width and height of the layout, in tiles.
+    pub fn size(&self) -> IVec2 {
+        let width = self.rows.iter().map(|row| row.chars().count()).max();
+        IVec2::new(width.unwrap_or(0) as i32, self.rows.len() as i32)
+    }
+
+    /// Tiles marked with the passed spawn point.
+    pub fn spawn_points(&self, spawn: SpawnPoint) -> Vec<IVec2> {
+        self.tiles()
+            .filter(|(_, entry)| entry.spawn == Some(spawn))
+            .map(|(point, _)| point)
+            .collect()
+    }
+}
+
+#[derive(Default)]
+pub struct LevelLoader;
+
+impl AssetLoader for LevelLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let level = ron::de::from_bytes::<Level>(bytes)?;
+            // Tiles off the map would be spawned, but never placed or torn down.
+            let size = level.size();
+            if size.x > WORLD_SIZE.x || size.y > WORLD_SIZE.y {
+                return Err(bevy::asset::Error::msg(format!(
+                    "Level is {}x{} tiles, but the map only fits {}x{}",
+                    size.x, size.y, WORLD_SIZE.x, WORLD_SIZE.y
+                )));
+            }
+            // Anything missing from the legend would leave a hole in the bar.
+            if let Some(c) = level
+                .rows
+                .iter()
+                .flat_map(|row| row.chars())
+                .find(|c| *c != ' ' && !level.legend.contains_key(c))
+            {
+                return Err(bevy::asset::Error::msg(format!(
+                    "Level has no legend entry for '{}'",
+                    c
+                )));
+            }
+            load_context.set_default_asset(LoadedAsset::new(level));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["level.ron"]
+    }
+}
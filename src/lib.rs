@@ -1,23 +1,53 @@
 mod actions;
 mod animate;
 mod audio;
+mod customers;
+mod highscores;
+mod level;
 mod loading;
 mod menu;
 mod npc;
+mod options;
+mod pause;
 mod player;
+mod recipe;
+mod replay;
+mod results;
+mod rng;
 mod score;
+mod settings;
+mod sheet;
+mod shift;
+mod simulation;
+mod storage;
+#[cfg(test)]
+mod testing;
 mod tilemap;
+mod tween;
 mod world;
+mod ysort;
 
-use crate::actions::ActionsPlugin;
+use crate::actions::{ActionsPlugin, ActiveGamepad, PlayMode};
 use crate::animate::AnimatePlugin;
-use crate::audio::InternalAudioPlugin;
+use crate::audio::{InternalAudioPlugin, SoundStates};
+use crate::customers::CustomersPlugin;
+use crate::highscores::HighScoresPlugin;
 use crate::loading::LoadingPlugin;
 use crate::menu::MenuPlugin;
 use crate::npc::NPCPlugin;
+pub use crate::options::LaunchOptions;
+use crate::pause::PausePlugin;
 use crate::player::PlayerPlugin;
+use crate::replay::ReplayPlugin;
+use crate::results::ResultsPlugin;
+use crate::rng::RngPlugin;
 use crate::score::ScorePlugin;
-use crate::world::WorldPlugin;
+use crate::settings::SettingsPlugin;
+use crate::shift::ShiftPlugin;
+pub use crate::simulation::{SimulationPlugin, SimulationSettings};
+use crate::tween::TweenPlugin;
+use crate::world::{SpawnWorld, WorldPlugin};
+use crate::ysort::YSortPlugin;
 
 use bevy::app::App;
 #[cfg(debug_assertions)]
@@ -37,22 +67,110 @@ enum GameState {
     Playing,
     // Here the menu is drawn and waiting for player interaction
     Menu,
+    // Game logic is frozen and the pause menu is drawn over the world
+    Paused,
+    // The shift is over and the results are drawn over the world
+    Results,
+    // Here the controls can be rebound
+    Settings,
+}
+
+/// Present while a session is under way, including while paused.
+#[derive(Resource)]
+struct Session;
+
+/// Marks entities spawned for a session. They are despawned, with their children, when the
+/// session is over.
+#[derive(Component)]
+pub struct SessionScoped;
+
+/// Run condition for systems that set up a session on entering `GameState::Playing`, so they
+/// don't run again when resuming from a pause.
+fn starting_session(session: Option<Res<Session>>) -> bool {
+    session.is_none()
+}
+
+// Marks the session started once the world is in place, so the setup systems ordered after that
+// still see it starting. The resource is inserted at the end of the schedule.
+fn start_session(mut commands: Commands) {
+    commands.insert_resource(Session);
+}
+
+// Runs when heading back to the menu, or when starting over without going through it. Starting
+// over, it runs before the new world is spawned, as that is put in place straight away for the
+// setup systems that follow. Entities spawned by other setup systems aren't in the query yet.
+fn teardown_session(mut commands: Commands, query: Query<Entity, With<SessionScoped>>) {
+    for entity in &query {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// The states, and the sessions played through them. Shared by the game and the simulation.
+fn add_sessions(app: &mut App) -> &mut App {
+    app.add_state::<GameState>()
+        .add_system(
+            start_session
+                .after(SpawnWorld)
+                .in_schedule(OnEnter(GameState::Playing)),
+        )
+        .add_system(teardown_session.in_schedule(OnEnter(GameState::Menu)))
+        .add_system(
+            teardown_session
+                .run_if(starting_session)
+                .before(SpawnWorld)
+                .in_schedule(OnEnter(GameState::Playing)),
+        )
+}
+
+/// Everything that plays the game, with stand-ins for the resources of the plugins that read
+/// input, draw menus or play audio. For running without a window, in the simulation and tests.
+fn add_headless_gameplay(app: &mut App) -> &mut App {
+    add_sessions(app)
+        .init_resource::<PlayMode>()
+        .init_resource::<ActiveGamepad>()
+        .init_resource::<SoundStates>()
+        .add_plugin(RngPlugin)
+        .add_plugin(AnimatePlugin)
+        .add_plugin(PlayerPlugin)
+        .add_plugin(NPCPlugin)
+        .add_plugin(ShiftPlugin)
+        .add_plugin(CustomersPlugin)
+        .add_plugin(ScorePlugin)
+        .add_plugin(TweenPlugin)
+        .add_plugin(WorldPlugin)
+        .add_plugin(YSortPlugin)
+}
+
+fn spawn_camera(mut commands: Commands) {
+    commands.spawn(Camera2dBundle::default());
 }
 
 pub struct GamePlugin;
 
 impl Plugin for GamePlugin {
     fn build(&self, app: &mut App) {
-        app.add_state::<GameState>()
+        app.init_resource::<LaunchOptions>();
+        add_sessions(app)
+            .add_startup_system(spawn_camera)
+            .add_plugin(RngPlugin)
             .add_plugin(AnimatePlugin)
             .add_plugin(LoadingPlugin)
             .add_plugin(MenuPlugin)
+            .add_plugin(SettingsPlugin)
+            .add_plugin(PausePlugin)
+            .add_plugin(ResultsPlugin)
             .add_plugin(ActionsPlugin)
             .add_plugin(InternalAudioPlugin)
             .add_plugin(PlayerPlugin)
             .add_plugin(NPCPlugin)
+            .add_plugin(ShiftPlugin)
+            .add_plugin(CustomersPlugin)
             .add_plugin(ScorePlugin)
-            .add_plugin(WorldPlugin);
+            .add_plugin(HighScoresPlugin)
+            .add_plugin(ReplayPlugin)
+            .add_plugin(TweenPlugin)
+            .add_plugin(WorldPlugin)
+            .add_plugin(YSortPlugin);
 
         #[cfg(debug_assertions)]
         {
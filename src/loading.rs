@@ -1,3 +1,6 @@
+use crate::level::{Level, LevelLoader};
+use crate::recipe::{RecipeBook, RecipeBookLoader};
+use crate::sheet::{SpriteSheet, SpriteSheetLoader};
 use crate::GameState;
 use bevy::prelude::*;
 use bevy_asset_loader::prelude::*;
@@ -10,19 +13,52 @@ pub struct LoadingPlugin;
 /// If interested, take a look at <https://bevy-cheatbook.github.io/features/assets.html>
 impl Plugin for LoadingPlugin {
     fn build(&self, app: &mut App) {
-        app.add_loading_state(
-            LoadingState::new(GameState::Loading).continue_to_state(GameState::Menu),
-        )
-        .add_collection_to_loading_state::<_, FontAssets>(GameState::Loading)
-        .add_collection_to_loading_state::<_, AudioAssets>(GameState::Loading)
-        .add_collection_to_loading_state::<_, TextureAssets>(GameState::Loading);
+        add_game_data(app)
+            .init_asset_loader::<SpriteSheetLoader>()
+            .add_loading_state(
+                LoadingState::new(GameState::Loading).continue_to_state(GameState::Menu),
+            )
+            .add_collection_to_loading_state::<_, FontAssets>(GameState::Loading)
+            .add_collection_to_loading_state::<_, AudioAssets>(GameState::Loading)
+            .add_collection_to_loading_state::<_, TextureAssets>(GameState::Loading)
+            .add_collection_to_loading_state::<_, SheetAssets>(GameState::Loading)
+            .add_collection_to_loading_state::<_, LevelAssets>(GameState::Loading)
+            .add_collection_to_loading_state::<_, RecipeAssets>(GameState::Loading);
     }
 }
 
+pub struct HeadlessLoadingPlugin;
+
+/// This plugin loads only the level, recipes and animation clips, for running without a window,
+/// and goes straight to playing. Nothing is drawn or heard, so fonts, textures and audio are left
+/// as empty handles.
+impl Plugin for HeadlessLoadingPlugin {
+    fn build(&self, app: &mut App) {
+        add_game_data(app)
+            .init_resource::<FontAssets>()
+            .init_resource::<TextureAssets>()
+            .add_asset_loader(SpriteSheetLoader { clips_only: true })
+            .add_loading_state(
+                LoadingState::new(GameState::Loading).continue_to_state(GameState::Playing),
+            )
+            .add_collection_to_loading_state::<_, SheetAssets>(GameState::Loading)
+            .add_collection_to_loading_state::<_, LevelAssets>(GameState::Loading)
+            .add_collection_to_loading_state::<_, RecipeAssets>(GameState::Loading);
+    }
+}
+
+fn add_game_data(app: &mut App) -> &mut App {
+    app.add_asset::<Level>()
+        .init_asset_loader::<LevelLoader>()
+        .add_asset::<RecipeBook>()
+        .init_asset_loader::<RecipeBookLoader>()
+        .add_asset::<SpriteSheet>()
+}
+
 // the following asset collections will be loaded during the State `GameState::Loading`
 // when done loading, they will be inserted as resources (see <https://github.com/NiklasEi/bevy_asset_loader>)
 
-#[derive(AssetCollection, Resource)]
+#[derive(AssetCollection, Resource, Default)]
 pub struct FontAssets {
     #[asset(path = "fonts/FiraSans-Bold.ttf")]
     pub fira_sans: Handle<Font>,
@@ -38,14 +74,8 @@ pub struct AudioAssets {
     pub chatter: Handle<AudioSource>,
 }
 
-#[derive(AssetCollection, Resource)]
+#[derive(AssetCollection, Resource, Default)]
 pub struct TextureAssets {
-    #[asset(texture_atlas(tile_size_x = 16., tile_size_y = 32., columns = 4, rows = 1))]
-    #[asset(path = "textures/luna-16x32.png")]
-    pub luna: Handle<TextureAtlas>,
-    #[asset(texture_atlas(tile_size_x = 16., tile_size_y = 32., columns = 26, rows = 1))]
-    #[asset(path = "textures/npc1-16x32.png")]
-    pub npc1: Handle<TextureAtlas>,
     #[asset(path = "textures/floor1-16x16.png")]
     pub floor1: Handle<Image>,
     #[asset(path = "textures/table-16x16.png")]
@@ -75,3 +105,26 @@ pub struct TextureAssets {
     #[asset(path = "textures/mixer-16x8.png")]
     pub mixer: Handle<Image>,
 }
+
+/// Character sprite sheets, with their atlases and animation clips.
+#[derive(AssetCollection, Resource)]
+pub struct SheetAssets {
+    #[asset(path = "sheets/luna.sheet.ron")]
+    pub luna: Handle<SpriteSheet>,
+    #[asset(path = "sheets/bartender2.sheet.ron")]
+    pub bartender2: Handle<SpriteSheet>,
+    #[asset(path = "sheets/npc1.sheet.ron")]
+    pub npc1: Handle<SpriteSheet>,
+}
+
+#[derive(AssetCollection, Resource)]
+pub struct LevelAssets {
+    #[asset(path = "levels/bar.level.ron")]
+    pub bar: Handle<Level>,
+}
+
+#[derive(AssetCollection, Resource)]
+pub struct RecipeAssets {
+    #[asset(path = "recipes/drinks.recipes.ron")]
+    pub book: Handle<RecipeBook>,
+}
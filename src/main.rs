@@ -3,10 +3,11 @@
 
 use bevy::prelude::*;
 use bevy::DefaultPlugins;
-use lunas_libations::GamePlugin;
+use lunas_libations::{GamePlugin, LaunchOptions};
 
 fn main() {
     App::new()
+        .insert_resource(LaunchOptions::load())
         .insert_resource(Msaa::Off)
         .insert_resource(ClearColor(Color::rgb(0.4, 0.4, 0.4)))
         .add_plugins(
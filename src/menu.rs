@@ -1,24 +1,26 @@
+use crate::actions::PlayMode;
+use crate::highscores::HighScores;
 use crate::loading::FontAssets;
 use crate::GameState;
 use bevy::prelude::*;
 
 pub struct MenuPlugin;
 
-/// This plugin is responsible for the game menu (containing only one button...)
+/// This plugin is responsible for the game menu (play, co-op and controls buttons, and the high-score table)
 /// The menu is only drawn during the State `GameState::Menu` and is removed when that state is exited
 impl Plugin for MenuPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<ButtonColors>()
             .add_system(setup_menu.in_schedule(OnEnter(GameState::Menu)))
-            .add_system(click_play_button.in_set(OnUpdate(GameState::Menu)))
+            .add_system(click_menu_buttons.in_set(OnUpdate(GameState::Menu)))
             .add_system(cleanup_menu.in_schedule(OnExit(GameState::Menu)));
     }
 }
 
 #[derive(Resource)]
-struct ButtonColors {
-    normal: Color,
-    hovered: Color,
+pub struct ButtonColors {
+    pub normal: Color,
+    pub hovered: Color,
 }
 
 impl Default for ButtonColors {
@@ -30,49 +32,123 @@ impl Default for ButtonColors {
     }
 }
 
+/// Buttons of kind `T` whose interaction changed this frame.
+pub type ButtonInteractions<'w, 's, T> = Query<
+    'w,
+    's,
+    (
+        &'static Interaction,
+        &'static mut BackgroundColor,
+        &'static T,
+    ),
+    (Changed<Interaction>, With<Button>),
+>;
+
+#[derive(Component)]
+struct Menu;
+
+#[derive(Component)]
+enum MenuButton {
+    Play,
+    Coop,
+    Controls,
+}
+
+impl MenuButton {
+    fn label(&self) -> &'static str {
+        match self {
+            MenuButton::Play => "Play",
+            MenuButton::Coop => "Co-op",
+            MenuButton::Controls => "Controls",
+        }
+    }
+}
+
 fn setup_menu(
     mut commands: Commands,
     font_assets: Res<FontAssets>,
     button_colors: Res<ButtonColors>,
+    high_scores: Res<HighScores>,
 ) {
-    commands.spawn(Camera2dBundle::default());
+    let text_style = |font_size| TextStyle {
+        font: font_assets.fira_sans.clone(),
+        font_size,
+        color: Color::rgb(0.9, 0.9, 0.9),
+    };
+
     commands
-        .spawn(ButtonBundle {
-            style: Style {
-                size: Size::new(Val::Px(120.0), Val::Px(50.0)),
-                margin: UiRect::all(Val::Auto),
-                justify_content: JustifyContent::Center,
-                align_items: AlignItems::Center,
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..Default::default()
+                },
                 ..Default::default()
             },
-            background_color: button_colors.normal.into(),
-            ..Default::default()
-        })
+            Menu,
+        ))
         .with_children(|parent| {
-            parent.spawn(TextBundle::from_section(
-                "Play",
-                TextStyle {
-                    font: font_assets.fira_sans.clone(),
-                    font_size: 40.0,
-                    color: Color::rgb(0.9, 0.9, 0.9),
-                },
-            ));
+            for button in [MenuButton::Play, MenuButton::Coop, MenuButton::Controls] {
+                parent
+                    .spawn(ButtonBundle {
+                        style: Style {
+                            size: Size::new(Val::Px(160.0), Val::Px(50.0)),
+                            margin: UiRect::all(Val::Px(10.0)),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..Default::default()
+                        },
+                        background_color: button_colors.normal.into(),
+                        ..Default::default()
+                    })
+                    .with_children(|parent| {
+                        parent.spawn(TextBundle::from_section(button.label(), text_style(40.0)));
+                    })
+                    .insert(button);
+            }
+
+            if high_scores.entries().is_empty() {
+                return;
+            }
+            parent.spawn(TextBundle::from_section("High scores", text_style(32.0)));
+            for (rank, entry) in high_scores.entries().iter().enumerate() {
+                parent.spawn(TextBundle::from_section(
+                    format!(
+                        "{:>2}. {:>5}  {} drinks  {}  {}",
+                        rank + 1,
+                        entry.score,
+                        entry.drinks_served,
+                        entry.level,
+                        entry.date
+                    ),
+                    text_style(20.0),
+                ));
+            }
         });
 }
 
-fn click_play_button(
+fn click_menu_buttons(
     button_colors: Res<ButtonColors>,
+    mut play_mode: ResMut<PlayMode>,
     mut state: ResMut<NextState<GameState>>,
-    mut interaction_query: Query<
-        (&Interaction, &mut BackgroundColor),
-        (Changed<Interaction>, With<Button>),
-    >,
+    mut interaction_query: ButtonInteractions<MenuButton>,
 ) {
-    for (interaction, mut color) in &mut interaction_query {
+    for (interaction, mut color, button) in &mut interaction_query {
         match *interaction {
-            Interaction::Clicked => {
-                state.set(GameState::Playing);
-            }
+            Interaction::Clicked => match button {
+                MenuButton::Play => {
+                    *play_mode = PlayMode::Solo;
+                    state.set(GameState::Playing);
+                }
+                MenuButton::Coop => {
+                    *play_mode = PlayMode::Coop;
+                    state.set(GameState::Playing);
+                }
+                MenuButton::Controls => state.set(GameState::Settings),
+            },
             Interaction::Hovered => {
                 *color = button_colors.hovered.into();
             }
@@ -83,6 +159,6 @@ fn click_play_button(
     }
 }
 
-fn cleanup_menu(mut commands: Commands, button: Query<Entity, With<Button>>) {
-    commands.entity(button.single()).despawn_recursive();
+fn cleanup_menu(mut commands: Commands, menu: Query<Entity, With<Menu>>) {
+    commands.entity(menu.single()).despawn_recursive();
 }
@@ -1,68 +1,97 @@
-use crate::animate::{Animation, AnimationComponent};
+use crate::actions::{Actions, InputSources};
+use crate::animate::{AnimationStateMachine, Performance};
 use crate::audio::SoundStates;
-use crate::loading::TextureAssets;
-use crate::player::{Beverage, Interactable, Item, Player, PlayerHeading};
-use crate::score::Score;
+use crate::loading::{RecipeAssets, TextureAssets};
+use crate::player::{Interactable, Item, Player, PlayerHeading};
+use crate::recipe::RecipeBook;
+use crate::rng::GameRng;
+use crate::score::{Score, ScoreEvent};
 use crate::tilemap::TileMap;
-use crate::world::Tile;
+use crate::world::{Passable, Tile};
 use crate::GameState;
 use bevy::prelude::*;
 use rand::prelude::*;
+use serde::Deserialize;
 
 pub struct NPCPlugin;
 
 impl Plugin for NPCPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system(setup_npc_animations.in_schedule(OnEnter(GameState::Playing)))
-            .add_systems(
-                (update_npc_stats, npc_move, npc_ai.after(npc_move))
-                    .in_set(OnUpdate(GameState::Playing)),
-            );
+        app.add_systems(
+            (
+                update_npc_stats,
+                npc_actions.in_set(InputSources),
+                npc_ai.after(npc_actions),
+                npc_patience.after(npc_ai),
+                update_patience_bars.after(npc_patience),
+            )
+                .in_set(OnUpdate(GameState::Playing)),
+        );
     }
 }
 
-#[derive(Component)]
-struct NPCAnimations {
-    dance: Animation,
-    talk_right: Animation,
-    talk_left: Animation,
-    puke: Animation,
-    cry: Animation,
-    punch_left: Animation,
-    punch_right: Animation,
-    drink: Animation,
-}
-
-impl NPCAnimations {
-    fn new() -> Self {
-        NPCAnimations {
-            dance: Animation::new(&[4, 5, 6, 7], 0.3, true),
-            talk_right: Animation::new(&[3, 9], 0.4, true),
-            talk_left: Animation::new(&[2, 8], 0.4, true),
-            puke: Animation::new(&[2, 10, 11, 12], 0.3, true),
-            cry: Animation::new(&[0, 13, 14, 15, 16, 17], 0.3, true),
-            punch_left: Animation::new(&[2, 18, 19, 20], 0.3, true),
-            punch_right: Animation::new(&[3, 21, 22, 23], 0.3, true),
-            drink: Animation::new(&[24, 25], 0.3, true),
-        }
-    }
-}
-
-fn setup_npc_animations(mut commands: Commands) {
-    commands.spawn(NPCAnimations::new());
-}
+/// Seconds an NPC will wait for their order before walking out.
+const PATIENCE: f32 = 30.;
+/// What an NPC pays for each drink.
+const DRINK_PRICE: u32 = 10;
+/// Offsets to the tiles beside one, where an NPC can stand to use it.
+const SIDES: [IVec2; 4] = [IVec2::NEG_Y, IVec2::Y, IVec2::NEG_X, IVec2::X];
 
 #[derive(Component, Default)]
 pub struct NPC {
     /// Current internal stats driving the AI.
     stats: Stats,
+    /// How much this NPC cares about each stat when ordering a drink.
+    taste: Stats,
     /// Goal to move to. If `None`, will stand still.
     move_to: Option<IVec2>,
+    /// Remaining waypoints towards `move_to`, the next one last.
+    path: Vec<IVec2>,
     behavior: Behavior,
     timer: Timer,
+    /// Drains while waiting for an order.
+    patience: Timer,
+    /// Money left to spend on drinks.
+    wallet: u32,
+    /// Drinks to have before leaving happy.
+    drinks_wanted: u32,
+}
+
+/// Shows how much patience the NPC has left, over their request bubble.
+#[derive(Component)]
+struct PatienceBar {
+    npc: Entity,
+}
+
+impl NPC {
+    pub fn new(rng: &mut impl Rng) -> Self {
+        NPC {
+            taste: Stats {
+                quench: rng.gen_range(0.5..1.5),
+                mood: rng.gen_range(0.5..1.5),
+                drunk: rng.gen_range(-0.5..1.5),
+            },
+            wallet: rng.gen_range(20..=60),
+            drinks_wanted: rng.gen_range(1..=4),
+            ..default()
+        }
+    }
+
+    /// Whether the NPC has ordered, and is still waiting to be served.
+    pub fn is_waiting(&self) -> bool {
+        matches!(self.behavior, Behavior::Request(_) | Behavior::Grab(_))
+    }
+
+    /// The order, once the NPC is at the bar waiting to grab it.
+    pub fn waiting_at_bar(&self) -> Option<&Item> {
+        match &self.behavior {
+            Behavior::Grab(order) => Some(order),
+            _ => None,
+        }
+    }
 }
 
-#[derive(Default, Copy, Clone, Debug)]
+#[derive(Default, Copy, Clone, Debug, Deserialize)]
 pub struct Stats {
     /// How quenched or thirsty. Negative is thirsy
     pub quench: f32,
@@ -92,14 +121,19 @@ impl std::ops::AddAssign for Stats {
 
 enum Behavior {
     Idle,
+    /// Walking to a container to order the item.
     Request(Item),
-    Grab,
-    Drink,
+    /// Waiting at a container for the ordered item.
+    Grab(Item),
+    /// Walking to a table to drink, with the ordered item.
+    Drink(Item),
     Chat,
     Fight,
     Dance,
     Cry,
     Puke,
+    /// Walking out the entrance, to leave for good.
+    Leave,
 }
 
 impl Default for Behavior {
@@ -130,6 +164,8 @@ fn update_npc_stats(time: Res<Time>, mut npc_query: Query<&mut NPC>) {
 fn npc_consume_drink(npc: &mut NPC, item: &Item) {
     if let Item::Beverage(beverage) = item {
         npc.stats += beverage.stats;
+        npc.wallet = npc.wallet.saturating_sub(DRINK_PRICE);
+        npc.drinks_wanted = npc.drinks_wanted.saturating_sub(1);
     } else {
         npc.stats.quench -= 10.;
         npc.stats.mood -= 5.;
@@ -137,10 +173,70 @@ fn npc_consume_drink(npc: &mut NPC, item: &Item) {
     }
 }
 
-fn npc_decide_next_action(npc: &NPC) -> Behavior {
+/// React to being served `served` after ordering `order`.
+fn npc_react_to_order(
+    entity: Entity,
+    npc: &mut NPC,
+    order: &Item,
+    served: &Item,
+    score: &mut Score,
+) {
+    match (order, served) {
+        (Item::Beverage(ordered), Item::Beverage(beverage)) if ordered.name == beverage.name => {
+            println!("Served the right drink, {}", beverage.name);
+            score.record(ScoreEvent::RightDrink, entity);
+            npc.stats.mood += 10.;
+        }
+        (_, Item::Beverage(beverage)) => {
+            println!("Served the wrong drink, {}", beverage.name);
+            score.record(ScoreEvent::WrongDrink, entity);
+            npc.stats.mood -= 5.;
+        }
+        _ => {
+            println!("Served a raw fruit");
+            score.record(ScoreEvent::RawFruit, entity);
+            npc.stats.mood -= 10.;
+        }
+    }
+}
+
+/// Pick the drink that best suits the NPC's taste and current state.
+fn npc_choose_order(npc: &NPC, recipes: &RecipeBook, rng: &mut impl Rng) -> Item {
+    // Sad NPCs want cheering up, drunk ones want to slow down.
+    let mood_weight = npc.taste.mood + if npc.stats.mood < 0. { 0.5 } else { 0. };
+    let drunk_weight = npc.taste.drunk - npc.stats.drunk / 30.;
+    let beverage = recipes
+        .recipes
+        .iter()
+        .map(|recipe| {
+            let appeal = recipe.stats.quench * npc.taste.quench
+                + recipe.stats.mood * mood_weight
+                + recipe.stats.drunk * drunk_weight
+                + rng.gen_range(0.0..10.0);
+            (appeal, recipe)
+        })
+        .max_by(|(a, _), (b, _)| a.total_cmp(b))
+        .map(|(_, recipe)| recipe.beverage())
+        .unwrap_or_default();
+    Item::Beverage(beverage)
+}
+
+fn npc_decide_next_action(npc: &NPC, recipes: &RecipeBook, rng: &mut impl Rng) -> Behavior {
     println!("Decide action, current NPC stats: {:?}", npc.stats);
+    if npc.stats.drunk >= 80. {
+        println!("Too drunk, leaving");
+        return Behavior::Leave;
+    }
     if npc.stats.quench <= 10. {
-        return Behavior::Request(Item::Beverage(Beverage::default()));
+        if npc.drinks_wanted == 0 {
+            println!("Satisfied, leaving");
+            return Behavior::Leave;
+        }
+        if npc.wallet < DRINK_PRICE {
+            println!("Broke, leaving");
+            return Behavior::Leave;
+        }
+        return Behavior::Request(npc_choose_order(npc, recipes, rng));
     }
 
     if npc.stats.drunk < 30. {
@@ -160,27 +256,68 @@ fn npc_decide_next_action(npc: &NPC) -> Behavior {
     }
 }
 
-fn npc_move(
-    mut query: Query<(&mut NPC, &mut Player, &Transform)>,
+/// The AI input source. NPCs walk and grab through their `Actions`, the same as players.
+fn npc_actions(
+    mut query: Query<(&mut NPC, &Player, &mut Actions, &Transform)>,
     tile_map_query: Query<(&TileMap, &Transform)>,
+    tile_query: Query<&Tile>,
 ) {
     let (tile_map, tile_map_transform) = tile_map_query.single();
 
-    for (mut npc, mut player, npc_transform) in &mut query {
+    for (mut npc, player, mut actions, npc_transform) in &mut query {
+        // Tap grab until the order is in hand.
+        let grabbing = matches!(npc.behavior, Behavior::Grab(_)) && player.holding.is_none();
+        let tap = grabbing && !actions.pick_up.pressed();
+        actions.pick_up.update(tap);
+        actions.interact.update(false);
+
         let Some(move_to) = npc.move_to else {
-            player.movement = None;
+            actions.movement = None;
             continue;
         };
 
         let npc_tile =
-            tile_map.camera_to_tile(tile_map_transform.translation, npc_transform.translation);
-        if npc_tile != move_to {
-            let movement = (move_to - npc_tile).as_vec2().normalize_or_zero();
-            player.movement = Some(movement);
-        } else {
+            tile_map.world_to_tile(tile_map_transform.translation, npc_transform.translation);
+        if npc_tile == move_to {
             npc.move_to = None;
-            player.movement = None;
+            npc.path.clear();
+            actions.movement = None;
+            continue;
+        }
+
+        if npc.path.last() == Some(&npc_tile) {
+            npc.path.pop();
         }
+
+        // Route again if the goal changed, or we have strayed from the path. Paths only take
+        // straight steps, so a waypoint off to the diagonal means we have been pushed aside.
+        let on_path = npc.path.last().is_some_and(|next| {
+            let step = (*next - npc_tile).abs();
+            step.x + step.y <= 1
+        });
+        if npc.path.first() != Some(&move_to) || !on_path {
+            let path = tile_map.find_path(npc_tile, move_to, |entity| {
+                let Ok(tile) = tile_query.get(entity) else {
+                    return false;
+                };
+                matches!(tile.passable(), Passable::Passable)
+            });
+            let Some(mut path) = path else {
+                println!("No path to {:?}, staying put", move_to);
+                npc.move_to = None;
+                npc.path.clear();
+                actions.movement = None;
+                continue;
+            };
+            path.reverse();
+            npc.path = path;
+        }
+
+        let Some(next) = npc.path.last() else {
+            continue;
+        };
+        let movement = (*next - npc_tile).as_vec2().normalize_or_zero();
+        actions.movement = Some(movement);
     }
 }
 
@@ -189,29 +326,34 @@ fn npc_ai(
     textures: Res<TextureAssets>,
     time: Res<Time>,
     mut score: ResMut<Score>,
+    mut rng: ResMut<GameRng>,
     mut sound_states: ResMut<SoundStates>,
-    npc_animations_query: Query<&NPCAnimations>,
     mut query: Query<(
         Entity,
         &mut NPC,
         &mut Player,
         &Transform,
-        &mut AnimationComponent,
+        &mut AnimationStateMachine,
     )>,
     tile_map_query: Query<(&TileMap, &Transform)>,
     interactable_query: Query<(Entity, &Interactable, &Parent)>,
     tile_query: Query<(&Tile, Option<&Children>)>,
     item_query: Query<&Item>,
+    recipe_assets: Res<RecipeAssets>,
+    recipe_books: Res<Assets<RecipeBook>>,
 ) {
     let (tile_map, tile_map_transform) = tile_map_query.single();
-    let npc_animations = npc_animations_query.single();
+    let Some(recipes) = recipe_books.get(&recipe_assets.book) else {
+        return;
+    };
 
     for (entity, mut npc, mut player, npc_transform, mut animation) in &mut query {
         let npc_tile =
-            tile_map.camera_to_tile(tile_map_transform.translation, npc_transform.translation);
+            tile_map.world_to_tile(tile_map_transform.translation, npc_transform.translation);
 
         npc.timer.tick(time.delta());
-        if !npc.timer.finished() {
+        // Whatever the NPC is playing out, like a chat or a dance, lasts as long as its animation.
+        if !npc.timer.finished() || animation.is_performing() {
             continue;
         }
 
@@ -219,270 +361,435 @@ fn npc_ai(
             Behavior::Idle => {
                 sound_states.drinking = false;
                 sound_states.chatting = false;
-                match npc_decide_next_action(&npc) {
-                    Behavior::Request(_) => {
+                match npc_decide_next_action(&npc, recipes, &mut *rng) {
+                    Behavior::Request(order) => {
                         npc_to_request(
                             &mut commands,
                             &textures,
+                            order,
                             entity,
                             &mut npc,
                             &mut player,
-                            &mut animation,
+                            npc_tile,
                             tile_map,
                             &interactable_query,
                             &tile_query,
+                            &mut *rng,
                         );
                     }
                     Behavior::Chat => {
-                        score.value += 10;
-                        npc_to_chat(&mut npc);
+                        score.record(ScoreEvent::Chat, entity);
+                        npc_to_chat(&mut npc, npc_tile, tile_map, &tile_query, &mut *rng);
                     }
                     Behavior::Fight => {
-                        score.value -= 20;
-                        npc_to_fight(&mut npc);
+                        score.record(ScoreEvent::Fight, entity);
+                        npc_to_fight(&mut npc, npc_tile, tile_map, &tile_query, &mut *rng);
                     }
                     Behavior::Dance => {
-                        score.value += 50;
-                        npc_to_dance(&mut npc);
+                        score.record(ScoreEvent::Dance, entity);
+                        npc_to_dance(&mut npc, npc_tile, tile_map, &tile_query, &mut *rng);
                     }
                     Behavior::Cry => {
-                        score.value -= 10;
-                        npc_to_cry(&mut npc);
+                        score.record(ScoreEvent::Cry, entity);
+                        npc_to_cry(&mut npc, npc_tile, tile_map, &tile_query, &mut *rng);
                     }
                     Behavior::Puke => {
-                        score.value -= 50;
-                        npc_to_puke(&mut npc);
+                        score.record(ScoreEvent::Puke, entity);
+                        npc_to_puke(&mut npc, npc_tile, tile_map, &tile_query, &mut *rng);
+                    }
+                    Behavior::Leave => {
+                        npc_to_leave(&mut npc, npc_tile, tile_map, &tile_query, &mut *rng);
                     }
                     _ => {}
                 }
             }
-            Behavior::Request(_item) => {
+            Behavior::Request(order) => {
                 if let None = npc.move_to {
                     println!("Update to grab");
-                    npc_to_grab(&mut npc, &mut player);
-                }
-            }
-            Behavior::Grab => {
-                if let Some(_) = player.holding {
-                    println!("Update to drink");
-                    npc_to_drink(
-                        &mut commands,
-                        entity,
+                    let order = order.clone();
+                    npc_to_grab(
                         &mut npc,
                         &mut player,
+                        order,
+                        npc_tile,
                         tile_map,
+                        &interactable_query,
                         &tile_query,
                     );
-                } else {
-                    player.pickup_action = true;
                 }
             }
-            Behavior::Drink => {
+            Behavior::Grab(order) => {
+                if let Some(_) = player.holding {
+                    println!("Update to drink");
+                    let order = order.clone();
+                    player.stop_requesting(entity, &mut commands);
+                    npc_to_drink(order, &mut npc, npc_tile, tile_map, &tile_query, &mut *rng);
+                }
+            }
+            Behavior::Drink(order) => {
                 let None = npc.move_to else {
                     continue;
                 };
                 println!("Drink");
-                npc_start_drinking(
+                let order = order.clone();
+                npc_take_drink(
                     &mut commands,
-                    &mut sound_states,
+                    entity,
+                    &mut score,
+                    &order,
                     &mut npc,
                     &mut player,
-                    &mut animation,
-                    npc_animations,
                     &item_query,
                 );
+                npc_start_drinking(&mut npc, &mut sound_states, &mut animation);
             }
             Behavior::Chat => {
                 let None = npc.move_to else {
                     continue;
                 };
                 println!("Chat");
-                npc_start_chat(&mut npc, &mut sound_states, &mut animation, npc_animations);
+                npc_start_chat(&mut npc, &mut sound_states, &mut animation);
             }
             Behavior::Fight => {
                 let None = npc.move_to else {
                     continue;
                 };
                 println!("Fight");
-                npc_start_fight(&mut npc, &mut animation, npc_animations);
+                npc_start_fight(&mut npc, &mut animation);
             }
             Behavior::Dance => {
                 let None = npc.move_to else {
                     continue;
                 };
                 println!("Dance");
-                npc_start_dance(&mut npc, &mut animation, npc_animations);
+                npc_start_dance(&mut npc, &mut animation);
             }
             Behavior::Cry => {
                 let None = npc.move_to else {
                     continue;
                 };
                 println!("Cry");
-                npc_start_cry(&mut npc, &mut animation, npc_animations);
+                npc_start_cry(&mut npc, &mut animation);
             }
             Behavior::Puke => {
                 let None = npc.move_to else {
                     continue;
                 };
                 println!("Puke");
-                npc_start_puke(&mut npc, &mut animation, npc_animations);
+                npc_start_puke(&mut npc, &mut animation);
+            }
+            Behavior::Leave => {
+                let None = npc.move_to else {
+                    continue;
+                };
+                println!("Left");
+                commands.entity(entity).despawn_recursive();
             }
         }
     }
 }
 
+fn npc_patience(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut score: ResMut<Score>,
+    mut rng: ResMut<GameRng>,
+    mut query: Query<(Entity, &mut NPC, &mut Player, &Transform)>,
+    tile_map_query: Query<(&TileMap, &Transform)>,
+    tile_query: Query<(&Tile, Option<&Children>)>,
+) {
+    let (tile_map, tile_map_transform) = tile_map_query.single();
+
+    for (entity, mut npc, mut player, npc_transform) in &mut query {
+        let (Behavior::Request(_) | Behavior::Grab(_)) = npc.behavior else {
+            continue;
+        };
+
+        npc.patience.tick(time.delta());
+        if npc.patience.just_finished() {
+            println!("Walk out");
+            score.record(ScoreEvent::WalkOut, entity);
+            let npc_tile =
+                tile_map.world_to_tile(tile_map_transform.translation, npc_transform.translation);
+            npc_walk_out(&mut commands, entity, &mut npc, &mut player);
+            npc_to_leave(&mut npc, npc_tile, tile_map, &tile_query, &mut *rng);
+        }
+    }
+}
+
+fn update_patience_bars(npc_query: Query<&NPC>, mut bar_query: Query<(&PatienceBar, &mut Sprite)>) {
+    for (bar, mut sprite) in &mut bar_query {
+        let Ok(npc) = npc_query.get(bar.npc) else {
+            continue;
+        };
+        let left = npc.patience.percent_left();
+        sprite.custom_size = Some(Vec2::new(16. * left, 2.));
+        sprite.color = Color::rgb(1. - left, left, 0.);
+    }
+}
+
 fn npc_to_request(
     commands: &mut Commands,
     textures: &Res<TextureAssets>,
+    order: Item,
     entity: Entity,
     npc: &mut NPC,
     player: &mut Player,
-    animation: &mut AnimationComponent,
+    npc_tile: IVec2,
     tile_map: &TileMap,
     interactable_query: &Query<(Entity, &Interactable, &Parent)>,
     tile_query: &Query<(&Tile, Option<&Children>)>,
+    rng: &mut impl Rng,
 ) {
-    animation.stop_animation();
-    // find a container
-    let containers = all_containers(&tile_map, &interactable_query, &tile_query);
-    let dest = if containers.len() > 0 {
-        let (point, _) = containers.choose(&mut rand::thread_rng()).unwrap();
-        Some(*point + IVec2::new(0, -1))
-    } else {
-        None
-    };
-    npc.move_to = dest;
-    player.request(
-        Item::Beverage(Beverage::default()),
-        entity,
-        commands,
-        textures,
-    );
-    npc.behavior = Behavior::Request(Item::Banana);
+    // find a container, and a side of it to wait on
+    let reachable = reachable_tiles(npc_tile, tile_map, tile_query);
+    let counters: Vec<IVec2> = all_containers(tile_map, interactable_query, tile_query)
+        .iter()
+        .flat_map(|(container, _)| SIDES.map(|side| *container + side))
+        .filter(|counter| reachable.contains(counter))
+        .collect();
+    npc.move_to = counters.choose(rng).copied();
+    let bubble = player.request(order.clone(), entity, commands, textures);
+    let patience_bar = commands
+        .spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    anchor: bevy::sprite::Anchor::BottomLeft,
+                    custom_size: Some(Vec2::new(16., 2.)),
+                    color: Color::GREEN,
+                    ..default()
+                },
+                transform: Transform::from_translation(Vec3::new(0., 10., 0.1)),
+                ..default()
+            },
+            PatienceBar { npc: entity },
+        ))
+        .id();
+    commands.entity(bubble).add_child(patience_bar);
+    npc.patience = Timer::from_seconds(PATIENCE, TimerMode::Once);
+    npc.behavior = Behavior::Request(order);
+}
+
+/// Give up waiting for an order, in a huff. They leave straight after.
+fn npc_walk_out(commands: &mut Commands, entity: Entity, npc: &mut NPC, player: &mut Player) {
+    player.stop_requesting(entity, commands);
+    npc.stats.mood -= 15.;
 }
 
-fn npc_to_grab(npc: &mut NPC, player: &mut Player) {
-    player.heading = PlayerHeading::Up;
-    npc.behavior = Behavior::Grab;
+/// Wait for the order, facing the container beside them.
+fn npc_to_grab(
+    npc: &mut NPC,
+    player: &mut Player,
+    order: Item,
+    npc_tile: IVec2,
+    tile_map: &TileMap,
+    interactable_query: &Query<(Entity, &Interactable, &Parent)>,
+    tile_query: &Query<(&Tile, Option<&Children>)>,
+) {
+    let containers = all_containers(tile_map, interactable_query, tile_query);
+    player.heading = SIDES
+        .into_iter()
+        .find(|side| {
+            containers
+                .iter()
+                .any(|(container, _)| *container == npc_tile + *side)
+        })
+        .map_or(PlayerHeading::Up, |side| {
+            PlayerHeading::from_vec(side.as_vec2())
+        });
+    npc.behavior = Behavior::Grab(order);
 }
 
 fn npc_to_drink(
-    commands: &mut Commands,
-    entity: Entity,
+    order: Item,
     npc: &mut NPC,
-    player: &mut Player,
+    npc_tile: IVec2,
     tile_map: &TileMap,
     tile_query: &Query<(&Tile, Option<&Children>)>,
+    rng: &mut impl Rng,
 ) {
-    player.stop_requesting(entity, commands);
-    let tables = all_tables(&tile_map, &tile_query);
-    let (dest, _) = tables.choose(&mut rand::thread_rng()).unwrap();
-    npc.move_to = Some(*dest + IVec2::new(0, 1));
-    npc.behavior = Behavior::Drink;
+    let reachable = reachable_tiles(npc_tile, tile_map, tile_query);
+    let seats: Vec<IVec2> = all_tables(tile_map, tile_query)
+        .iter()
+        .flat_map(|(table, _)| SIDES.map(|side| *table + side))
+        .filter(|seat| reachable.contains(seat))
+        .collect();
+    match seats.choose(rng) {
+        Some(seat) => npc.move_to = Some(*seat),
+        // Without a table to get to, anywhere will do.
+        None => move_to_random_space(npc, npc_tile, tile_map, tile_query, rng),
+    }
+    npc.behavior = Behavior::Drink(order);
 }
 
-fn npc_start_drinking(
+/// Finish off what they were served, and score it against what they ordered.
+fn npc_take_drink(
     commands: &mut Commands,
-    sound_states: &mut ResMut<SoundStates>,
+    entity: Entity,
+    score: &mut Score,
+    order: &Item,
     npc: &mut NPC,
     player: &mut Player,
-    animation: &mut AnimationComponent,
-    npc_animations: &NPCAnimations,
     item_query: &Query<&Item>,
 ) {
     if let Some(holding) = std::mem::replace(&mut player.holding, None) {
         if let Ok(item) = item_query.get(holding) {
             npc_consume_drink(npc, item);
+            npc_react_to_order(entity, npc, order, item, score);
         }
         commands.entity(holding).remove_parent().despawn();
     }
-    animation.start_animation(&npc_animations.drink);
+}
+
+fn npc_start_drinking(
+    npc: &mut NPC,
+    sound_states: &mut ResMut<SoundStates>,
+    animation: &mut AnimationStateMachine,
+) {
     sound_states.drinking = true;
     npc.behavior = Behavior::Idle;
-    npc.timer = Timer::from_seconds(3.5, TimerMode::Once);
+    animation.perform(Performance::Drink);
 }
 
-fn npc_to_chat(npc: &mut NPC) {
-    move_to_random_space(npc);
+fn npc_to_chat(
+    npc: &mut NPC,
+    npc_tile: IVec2,
+    tile_map: &TileMap,
+    tile_query: &Query<(&Tile, Option<&Children>)>,
+    rng: &mut impl Rng,
+) {
+    move_to_random_space(npc, npc_tile, tile_map, tile_query, rng);
     npc.behavior = Behavior::Chat;
 }
 
 fn npc_start_chat(
     npc: &mut NPC,
     sound_states: &mut ResMut<SoundStates>,
-    animation: &mut AnimationComponent,
-    npc_animations: &NPCAnimations,
+    animation: &mut AnimationStateMachine,
 ) {
-    animation.start_animation(&npc_animations.talk_right);
     sound_states.chatting = true;
     npc.behavior = Behavior::Idle;
-    npc.timer = Timer::from_seconds(6.5, TimerMode::Once);
+    animation.perform(Performance::Talk);
 }
 
-fn npc_to_fight(npc: &mut NPC) {
-    move_to_random_space(npc);
+fn npc_to_fight(
+    npc: &mut NPC,
+    npc_tile: IVec2,
+    tile_map: &TileMap,
+    tile_query: &Query<(&Tile, Option<&Children>)>,
+    rng: &mut impl Rng,
+) {
+    move_to_random_space(npc, npc_tile, tile_map, tile_query, rng);
     npc.behavior = Behavior::Fight;
 }
 
-fn npc_start_fight(
-    npc: &mut NPC,
-    animation: &mut AnimationComponent,
-    npc_animations: &NPCAnimations,
-) {
-    animation.start_animation(&npc_animations.punch_right);
+fn npc_start_fight(npc: &mut NPC, animation: &mut AnimationStateMachine) {
     npc.behavior = Behavior::Idle;
-    npc.timer = Timer::from_seconds(2.5, TimerMode::Once);
+    animation.perform(Performance::Fight);
 }
 
-fn npc_to_dance(npc: &mut NPC) {
-    move_to_random_space(npc);
+fn npc_to_dance(
+    npc: &mut NPC,
+    npc_tile: IVec2,
+    tile_map: &TileMap,
+    tile_query: &Query<(&Tile, Option<&Children>)>,
+    rng: &mut impl Rng,
+) {
+    move_to_random_space(npc, npc_tile, tile_map, tile_query, rng);
     npc.behavior = Behavior::Dance;
 }
 
-fn npc_start_dance(
-    npc: &mut NPC,
-    animation: &mut AnimationComponent,
-    npc_animations: &NPCAnimations,
-) {
-    animation.start_animation(&npc_animations.dance);
+fn npc_start_dance(npc: &mut NPC, animation: &mut AnimationStateMachine) {
     npc.behavior = Behavior::Idle;
-    npc.timer = Timer::from_seconds(10.5, TimerMode::Once);
+    animation.perform(Performance::Dance);
 }
 
-fn npc_to_cry(npc: &mut NPC) {
-    move_to_random_space(npc);
+fn npc_to_cry(
+    npc: &mut NPC,
+    npc_tile: IVec2,
+    tile_map: &TileMap,
+    tile_query: &Query<(&Tile, Option<&Children>)>,
+    rng: &mut impl Rng,
+) {
+    move_to_random_space(npc, npc_tile, tile_map, tile_query, rng);
     npc.behavior = Behavior::Cry;
 }
 
-fn npc_start_cry(
+fn npc_start_cry(npc: &mut NPC, animation: &mut AnimationStateMachine) {
+    npc.behavior = Behavior::Idle;
+    animation.perform(Performance::Cry);
+}
+
+fn npc_to_puke(
     npc: &mut NPC,
-    animation: &mut AnimationComponent,
-    npc_animations: &NPCAnimations,
+    npc_tile: IVec2,
+    tile_map: &TileMap,
+    tile_query: &Query<(&Tile, Option<&Children>)>,
+    rng: &mut impl Rng,
 ) {
-    animation.start_animation(&npc_animations.cry);
+    move_to_random_space(npc, npc_tile, tile_map, tile_query, rng);
+    npc.behavior = Behavior::Puke;
+}
+
+fn npc_start_puke(npc: &mut NPC, animation: &mut AnimationStateMachine) {
     npc.behavior = Behavior::Idle;
-    npc.timer = Timer::from_seconds(9.5, TimerMode::Once);
+    animation.perform(Performance::Puke);
 }
 
-fn npc_to_puke(npc: &mut NPC) {
-    move_to_random_space(npc);
-    npc.behavior = Behavior::Puke;
+/// Head for the nearest way out. Without one to get to, they wander off somewhere and go from
+/// there, rather than vanishing on the spot.
+fn npc_to_leave(
+    npc: &mut NPC,
+    npc_tile: IVec2,
+    tile_map: &TileMap,
+    tile_query: &Query<(&Tile, Option<&Children>)>,
+    rng: &mut impl Rng,
+) {
+    let reachable = reachable_tiles(npc_tile, tile_map, tile_query);
+    npc.move_to = all_entrances(tile_map, tile_query)
+        .into_iter()
+        .map(|(point, _)| point)
+        .filter(|point| reachable.contains(point))
+        .min_by_key(|point| {
+            let distance = (*point - npc_tile).abs();
+            distance.x + distance.y
+        });
+    if npc.move_to.is_none() {
+        move_to_random_space(npc, npc_tile, tile_map, tile_query, rng);
+    }
+    npc.behavior = Behavior::Leave;
 }
 
-fn npc_start_puke(
+/// Somewhere on the floor the NPC can walk to, or nowhere if they're boxed in.
+fn move_to_random_space(
     npc: &mut NPC,
-    animation: &mut AnimationComponent,
-    npc_animations: &NPCAnimations,
+    npc_tile: IVec2,
+    tile_map: &TileMap,
+    tile_query: &Query<(&Tile, Option<&Children>)>,
+    rng: &mut impl Rng,
 ) {
-    animation.start_animation(&npc_animations.puke);
-    npc.behavior = Behavior::Idle;
-    npc.timer = Timer::from_seconds(6.0, TimerMode::Once);
+    let tile = |entity| tile_query.get(entity).ok().map(|(tile, _)| tile);
+    let spaces: Vec<IVec2> = reachable_tiles(npc_tile, tile_map, tile_query)
+        .into_iter()
+        .filter(|point| {
+            *point != npc_tile
+                && matches!(tile_map.tile_at(*point).and_then(tile), Some(Tile::Floor))
+        })
+        .collect();
+    npc.move_to = spaces.choose(rng).copied();
 }
 
-fn move_to_random_space(npc: &mut NPC) {
-    let mut rng = thread_rng();
-    let tile = IVec2::new(rng.gen_range(4..20), rng.gen_range(5..10));
-    npc.move_to = Some(tile);
+/// Every tile the NPC can walk to from where they are.
+fn reachable_tiles(
+    npc_tile: IVec2,
+    tile_map: &TileMap,
+    tile_query: &Query<(&Tile, Option<&Children>)>,
+) -> Vec<IVec2> {
+    tile_map.reachable(npc_tile, |entity| {
+        tile_query
+            .get(entity)
+            .is_ok_and(|(tile, _)| matches!(tile.passable(), Passable::Passable))
+    })
 }
 
 fn all_containers(
@@ -531,3 +838,142 @@ fn all_tables(
         })
         .collect()
 }
+
+fn all_entrances(
+    tile_map: &TileMap,
+    tile_query: &Query<(&Tile, Option<&Children>)>,
+) -> Vec<(IVec2, Entity)> {
+    tile_map
+        .iter()
+        .filter(|(_, entity)| matches!(tile_query.get(*entity), Ok((Tile::Entrance, _))))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestApp;
+    use std::time::Duration;
+
+    fn behavior(world: &World, npc: Entity) -> &Behavior {
+        &world.get::<NPC>(npc).unwrap().behavior
+    }
+
+    #[test]
+    fn npc_orders_a_drink_from_the_recipe_book() {
+        let mut test = TestApp::new(&["BoB..", ".P...", "=mu..", "....."]);
+        let npc = test.waiting_customer(IVec2::new(2, 0));
+
+        let world = test.world();
+        let Some(Item::Beverage(order)) = world.get::<NPC>(npc).unwrap().waiting_at_bar() else {
+            panic!("The customer didn't order a drink");
+        };
+        let book = &world.resource::<RecipeAssets>().book;
+        let recipes = world.resource::<Assets<RecipeBook>>().get(book).unwrap();
+        assert!(recipes
+            .recipes
+            .iter()
+            .any(|recipe| recipe.name == order.name));
+
+        // The bubble over their head shows what they ordered.
+        let requesting = world.get::<Player>(npc).unwrap().requesting.unwrap();
+        let Some(Item::Beverage(shown)) = world.get::<Item>(requesting) else {
+            panic!("The customer isn't showing a drink");
+        };
+        assert_eq!(shown.name, order.name);
+    }
+
+    #[test]
+    fn served_npc_goes_from_grab_to_drink() {
+        // Nothing is above the table, so the customer has to sit beside or below it.
+        let mut test = TestApp::new(&["BoB.T", ".P...", "=mu..", "....."]);
+        let npc = test.waiting_customer(IVec2::new(2, 0));
+
+        // Mix an orange juice, and put it in the container in front of the customer.
+        let luna = test.bartender(0);
+        test.face(luna, Vec2::Y);
+        test.pick_up(luna);
+        test.face(luna, Vec2::NEG_Y);
+        test.pick_up(luna);
+        test.interact(luna);
+        test.pick_up(luna);
+        test.walk_to(luna, IVec2::new(2, 2));
+        test.face(luna, Vec2::NEG_Y);
+        test.pick_up(luna);
+
+        test.run_until("the customer takes the drink", |world| {
+            matches!(behavior(world, npc), Behavior::Drink(_))
+        });
+        let player = test.world().get::<Player>(npc).unwrap();
+        assert!(player.holding.is_some());
+        assert!(player.requesting.is_none());
+        let seat = test.world().get::<NPC>(npc).unwrap().move_to;
+        assert!(matches!(
+            seat,
+            Some(IVec2 { x: 3, y: 3 } | IVec2 { x: 4, y: 2 })
+        ));
+    }
+
+    #[test]
+    fn npc_orders_from_a_reachable_side_of_the_container() {
+        // The container's south side is walled off.
+        let mut test = TestApp::new(&["BoB..", ".P...", "=mu..", "..B.."]);
+        let npc = test.waiting_customer(IVec2::new(3, 0));
+
+        let heading = test.world().get::<Player>(npc).unwrap().heading;
+        assert_eq!(test.tile_of(npc) + heading.as_offset(), IVec2::new(2, 1));
+    }
+
+    #[test]
+    fn npc_pushed_off_their_path_routes_again() {
+        let mut test = TestApp::new(&["P....", "..B..", ".....", "....."]);
+        let npc = test.spawn_npc(IVec2::new(1, 1));
+        let mut customer = test.world().get_mut::<NPC>(npc).unwrap();
+        customer.behavior = Behavior::Chat;
+        customer.move_to = Some(IVec2::new(3, 3));
+        customer.path = vec![IVec2::new(3, 3), IVec2::new(3, 2)];
+
+        // Next to the wall, with the next waypoint around its corner.
+        test.place(npc, IVec2::new(2, 1));
+        test.run(1);
+        let path = &test.world().get::<NPC>(npc).unwrap().path;
+        assert_eq!(path.last(), Some(&IVec2::new(3, 1)));
+    }
+
+    #[test]
+    fn npc_that_runs_out_of_patience_walks_out() {
+        let mut test = TestApp::new(&["BoB..", ".P...", "=mu..", "E...."]);
+        let npc = test.waiting_customer(IVec2::new(2, 0));
+
+        // Nearly out of patience, rather than waiting it all out.
+        let mut customer = test.world().get_mut::<NPC>(npc).unwrap();
+        let patience = customer.patience.duration();
+        customer
+            .patience
+            .set_elapsed(patience - Duration::from_millis(100));
+        test.run_until("the customer walks out", |world| {
+            matches!(behavior(world, npc), Behavior::Leave)
+        });
+        assert_eq!(
+            test.world().resource::<Score>().count(ScoreEvent::WalkOut),
+            1
+        );
+        let player = test.world().get::<Player>(npc).unwrap();
+        assert!(player.requesting.is_none());
+    }
+
+    #[test]
+    fn broke_npc_leaves_from_the_floor_without_an_entrance() {
+        let mut test = TestApp::new(&["BoB..", ".P...", "=mu..", "....."]);
+        let npc = test.spawn_npc(IVec2::new(2, 0));
+        test.world().get_mut::<NPC>(npc).unwrap().wallet = 0;
+        test.run_until("the customer heads off", |world| {
+            matches!(behavior(world, npc), Behavior::Leave)
+        });
+        assert!(test.world().get::<NPC>(npc).unwrap().move_to.is_some());
+
+        test.run_until("the customer is gone", |world| {
+            world.get_entity(npc).is_none()
+        });
+    }
+}
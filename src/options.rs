@@ -0,0 +1,90 @@
+use crate::storage::{self, Location};
+use bevy::prelude::*;
+use serde::Deserialize;
+use std::path::PathBuf;
+
+const FILE_NAME: &str = "options.ron";
+
+/// Options given on the command line, or in `options.ron` in the config directory. The command
+/// line wins where both give one.
+#[derive(Resource, Deserialize, Default, Clone, Debug)]
+#[serde(default)]
+pub struct LaunchOptions {
+    /// Record each session to this file, to be replayed with `--replay`.
+    pub record: Option<PathBuf>,
+    /// Play back a recorded session instead of reading the controls.
+    pub replay: Option<PathBuf>,
+    /// Seed every session with this, instead of a new random seed each time.
+    pub seed: Option<u64>,
+    /// Seed every session with today's date, so everyone plays the same shift that day.
+    pub daily: bool,
+}
+
+impl LaunchOptions {
+    /// Reads the config file, then the process arguments over it.
+    pub fn load() -> Self {
+        let mut options: LaunchOptions =
+            storage::load(Location::Config, FILE_NAME).unwrap_or_default();
+        options.read_args(std::env::args().skip(1));
+        options
+    }
+
+    /// Unknown or malformed arguments are skipped with a warning, so a typo doesn't stop the
+    /// game from starting. `--seed` and `--daily` each replace the other, wherever it was given.
+    fn read_args(&mut self, mut args: impl Iterator<Item = String>) {
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--record" => self.record = args.next().map(PathBuf::from),
+                "--replay" => self.replay = args.next().map(PathBuf::from),
+                "--seed" => match args.next().map(|seed| seed.parse()) {
+                    Some(Ok(seed)) => {
+                        self.seed = Some(seed);
+                        self.daily = false;
+                    }
+                    _ => println!("--seed needs a number"),
+                },
+                "--daily" => {
+                    self.daily = true;
+                    self.seed = None;
+                }
+                _ => println!("Ignoring unknown argument {}", arg),
+            }
+        }
+    }
+
+    /// Whether systems must run in the same order every frame, so a session plays out the same
+    /// way when replayed.
+    pub fn deterministic(&self) -> bool {
+        self.record.is_some() || self.replay.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_args(options: &mut LaunchOptions, args: &[&str]) {
+        options.read_args(args.iter().map(|arg| arg.to_string()));
+    }
+
+    #[test]
+    fn arguments_override_the_config_file() {
+        let mut options = LaunchOptions {
+            seed: Some(7),
+            ..default()
+        };
+        read_args(&mut options, &["--daily"]);
+        assert_eq!((options.seed, options.daily), (None, true));
+
+        read_args(&mut options, &["--seed", "42", "--record", "shift.ron"]);
+        assert_eq!((options.seed, options.daily), (Some(42), false));
+        assert_eq!(options.record, Some(PathBuf::from("shift.ron")));
+    }
+
+    #[test]
+    fn bad_arguments_are_skipped() {
+        let mut options = LaunchOptions::default();
+        read_args(&mut options, &["--seed", "tomorrow", "--bogus", "--daily"]);
+        assert_eq!((options.seed, options.daily), (None, true));
+    }
+}
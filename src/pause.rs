@@ -0,0 +1,137 @@
+use crate::actions::{ControlInput, GameControl};
+use crate::loading::FontAssets;
+use crate::menu::{ButtonColors, ButtonInteractions};
+use crate::{GameState, Session};
+use bevy::prelude::*;
+
+pub struct PausePlugin;
+
+/// This plugin pauses the game, and draws the pause menu over the frozen world.
+/// Gameplay systems only run during `GameState::Playing`, so they stop while paused.
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(pause_game.in_set(OnUpdate(GameState::Playing)))
+            .add_system(setup_pause_menu.in_schedule(OnEnter(GameState::Paused)))
+            .add_systems((resume_game, click_pause_buttons).in_set(OnUpdate(GameState::Paused)))
+            .add_system(cleanup_pause_menu.in_schedule(OnExit(GameState::Paused)));
+    }
+}
+
+#[derive(Component)]
+struct PauseMenu;
+
+#[derive(Component)]
+enum PauseButton {
+    Resume,
+    Restart,
+    Quit,
+}
+
+impl PauseButton {
+    fn label(&self) -> &'static str {
+        match self {
+            PauseButton::Resume => "Resume",
+            PauseButton::Restart => "Restart",
+            PauseButton::Quit => "Quit",
+        }
+    }
+}
+
+fn pause_game(input: ControlInput, mut state: ResMut<NextState<GameState>>) {
+    if GameControl::Pause.just_pressed(&input) {
+        state.set(GameState::Paused);
+    }
+}
+
+fn resume_game(input: ControlInput, mut state: ResMut<NextState<GameState>>) {
+    if GameControl::Pause.just_pressed(&input) {
+        state.set(GameState::Playing);
+    }
+}
+
+fn setup_pause_menu(
+    mut commands: Commands,
+    font_assets: Res<FontAssets>,
+    button_colors: Res<ButtonColors>,
+) {
+    let text_style = |font_size| TextStyle {
+        font: font_assets.fira_sans.clone(),
+        font_size,
+        color: Color::rgb(0.9, 0.9, 0.9),
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                    position_type: PositionType::Absolute,
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..Default::default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.6).into(),
+                ..Default::default()
+            },
+            PauseMenu,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section("Paused", text_style(60.0)));
+            for button in [PauseButton::Resume, PauseButton::Restart, PauseButton::Quit] {
+                parent
+                    .spawn(ButtonBundle {
+                        style: Style {
+                            size: Size::new(Val::Px(200.0), Val::Px(50.0)),
+                            margin: UiRect::all(Val::Px(8.0)),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..Default::default()
+                        },
+                        background_color: button_colors.normal.into(),
+                        ..Default::default()
+                    })
+                    .with_children(|parent| {
+                        parent.spawn(TextBundle::from_section(button.label(), text_style(40.0)));
+                    })
+                    .insert(button);
+            }
+        });
+}
+
+fn click_pause_buttons(
+    mut commands: Commands,
+    button_colors: Res<ButtonColors>,
+    mut state: ResMut<NextState<GameState>>,
+    mut interaction_query: ButtonInteractions<PauseButton>,
+) {
+    for (interaction, mut color, button) in &mut interaction_query {
+        match *interaction {
+            Interaction::Clicked => match button {
+                PauseButton::Resume => {
+                    state.set(GameState::Playing);
+                }
+                PauseButton::Restart => {
+                    commands.remove_resource::<Session>();
+                    state.set(GameState::Playing);
+                }
+                PauseButton::Quit => {
+                    commands.remove_resource::<Session>();
+                    state.set(GameState::Menu);
+                }
+            },
+            Interaction::Hovered => {
+                *color = button_colors.hovered.into();
+            }
+            Interaction::None => {
+                *color = button_colors.normal.into();
+            }
+        }
+    }
+}
+
+fn cleanup_pause_menu(mut commands: Commands, menu: Query<Entity, With<PauseMenu>>) {
+    for entity in &menu {
+        commands.entity(entity).despawn_recursive();
+    }
+}
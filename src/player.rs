@@ -1,17 +1,38 @@
-use crate::actions::Actions;
-use crate::animate::AnimationComponent;
-use crate::loading::TextureAssets;
+use crate::actions::{Actions, ActiveGamepad, InputSource, InputSources, PlayMode};
+use crate::animate::{Animating, AnimationComponent, AnimationStateMachine};
+use crate::level::{Level, SpawnPoint};
+use crate::loading::{LevelAssets, RecipeAssets, SheetAssets, TextureAssets};
 use crate::npc::{Stats, NPC};
+use crate::recipe::RecipeBook;
+use crate::replay::Replay;
+use crate::rng::GameRng;
+use crate::sheet::SpriteSheet;
 use crate::tilemap::TileMap;
-use crate::world::{Passable, Tile, SCALE};
-use crate::GameState;
+use crate::tween::{Ease, Tween};
+use crate::world::{Passable, SpawnWorld, Tile, SCALE};
+use crate::ysort::YSort;
+use crate::{starting_session, GameState, SessionScoped};
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
-use rand::prelude::*;
+use rand::Rng;
+use serde::Deserialize;
 
 pub struct PlayerPlugin;
 
+/// Pixels a second, at full tilt.
+const WALK_SPEED: f32 = 150.;
+
+/// Where an item sits in a container, from its bottom left corner.
+const CONTAINER_SPOT: Vec2 = Vec2::new(0., 4.);
+/// Where items disappear into the mixer, shrunk down to `MIXED_SCALE`.
+const MIXER_MOUTH: Vec2 = Vec2::new(6., 4.);
+const MIXED_SCALE: f32 = 0.2;
+
+/// A bartender, numbered in the order they were spawned.
 #[derive(Component)]
-pub struct UserControllable;
+pub struct UserControllable {
+    pub player: usize,
+}
 
 #[derive(Component)]
 pub struct Player {
@@ -19,6 +40,8 @@ pub struct Player {
     pub holding: Option<Entity>,
     pub requesting: Option<Entity>,
     pub heading: PlayerHeading,
+    /// How fast they moved last frame, in pixels a second. Zero when walking into something.
+    pub speed: f32,
     pub pickup_action: bool,
     pub interact_action: bool,
 }
@@ -30,6 +53,7 @@ impl Default for Player {
             holding: None,
             requesting: None,
             heading: PlayerHeading::Down,
+            speed: 0.,
             pickup_action: false,
             interact_action: false,
         }
@@ -42,16 +66,18 @@ impl Player {
         self.holding = Some(item_entity);
     }
 
+    /// Show `item` in a bubble over the player, returning the bubble entity.
     pub fn request(
         &mut self,
         item: Item,
         player_entity: Entity,
         commands: &mut Commands,
         textures: &Res<TextureAssets>,
-    ) {
+    ) -> Entity {
         let item_entity = item.spawn(Vec3::new(-8., 32., 0.), commands, textures);
         commands.entity(player_entity).add_child(item_entity);
         self.requesting = Some(item_entity);
+        item_entity
     }
 
     pub fn stop_requesting(&mut self, player_entity: Entity, commands: &mut Commands) {
@@ -59,11 +85,13 @@ impl Player {
             return;
         };
 
-        commands.entity(item_entity).remove_parent().despawn();
+        commands.entity(item_entity).despawn_recursive();
     }
 }
 
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
 pub enum PlayerHeading {
+    #[default]
     Down,
     Up,
     Left,
@@ -71,7 +99,7 @@ pub enum PlayerHeading {
 }
 
 impl PlayerHeading {
-    fn from_vec(movement: Vec2) -> Self {
+    pub fn from_vec(movement: Vec2) -> Self {
         if movement.y < 0. {
             PlayerHeading::Down
         } else if movement.y > 0. {
@@ -85,16 +113,17 @@ impl PlayerHeading {
         }
     }
 
-    fn sprite_index(&self) -> usize {
+    /// How the heading is written in clip names.
+    pub fn name(&self) -> &'static str {
         match self {
-            PlayerHeading::Down => 0,
-            PlayerHeading::Up => 1,
-            PlayerHeading::Left => 2,
-            PlayerHeading::Right => 3,
+            PlayerHeading::Down => "down",
+            PlayerHeading::Up => "up",
+            PlayerHeading::Left => "left",
+            PlayerHeading::Right => "right",
         }
     }
 
-    fn as_offset(&self) -> IVec2 {
+    pub fn as_offset(&self) -> IVec2 {
         match self {
             PlayerHeading::Down => IVec2::new(0, -1),
             PlayerHeading::Up => IVec2::new(0, 1),
@@ -145,6 +174,7 @@ impl Interactable {
                     ..default()
                 },
                 self,
+                YSort::ON_TOP,
             ))
             .id()
     }
@@ -182,7 +212,11 @@ impl Interactable {
 
                 let item_entity = std::mem::replace(&mut container.holding, None).unwrap();
 
-                commands.entity(item_entity).remove_parent();
+                // Picked up before it had settled.
+                commands
+                    .entity(item_entity)
+                    .remove_parent()
+                    .remove::<Tween>();
 
                 Some(item_entity)
             }
@@ -201,8 +235,17 @@ impl Interactable {
             Interactable::Spawner(_) => false,
             Interactable::Mixer(mixer) => {
                 mixer.add(item);
-                commands.entity(item_entity).remove_parent();
-                commands.entity(item_entity).despawn();
+                // It's already in the mix. This just shows it going in.
+                commands
+                    .entity(item_entity)
+                    .set_parent_in_place(entity)
+                    .insert(
+                        Tween::new(0.3, Ease::QuadIn)
+                            .move_to(MIXER_MOUTH)
+                            .scale_to(Vec3::splat(MIXED_SCALE))
+                            .rotate_to(Quat::from_rotation_z(-0.5))
+                            .on_complete(|commands, entity| commands.entity(entity).despawn()),
+                    );
                 true
             }
             Interactable::Container(container) => {
@@ -210,8 +253,14 @@ impl Interactable {
                     return false;
                 };
                 container.holding = Some(item_entity);
-                commands.entity(item_entity).remove_parent();
-                commands.entity(entity).add_child(item_entity);
+                commands
+                    .entity(item_entity)
+                    .set_parent_in_place(entity)
+                    .insert(
+                        Tween::new(0.25, Ease::BackOut)
+                            .move_to(CONTAINER_SPOT)
+                            .scale_to(Vec3::ONE),
+                    );
 
                 true
             }
@@ -223,10 +272,10 @@ impl Interactable {
         }
     }
 
-    pub fn interact(&mut self) -> bool {
+    pub fn interact(&mut self, recipes: &RecipeBook, rng: &mut impl Rng) -> bool {
         match self {
             Interactable::Spawner(_) => false,
-            Interactable::Mixer(mixer) => mixer.mix(),
+            Interactable::Mixer(mixer) => mixer.mix(recipes, rng),
             Interactable::Container(_) => false,
             Interactable::Trash => false,
         }
@@ -250,34 +299,13 @@ impl Mixer {
         self.contains.push(item);
     }
 
-    pub fn mix(&mut self) -> bool {
+    pub fn mix(&mut self, recipes: &RecipeBook, rng: &mut impl Rng) -> bool {
         if self.contains.len() > 0 {
-            let mut rng = thread_rng();
-
-            let mut stats = Stats::default();
-            for item in &self.contains {
-                match item {
-                    Item::Orange => {
-                        stats.quench += 20. + rng.gen_range(-4.0..4.0);
-                        stats.mood += 5. + rng.gen_range(-1.2..1.8);
-                        stats.drunk += 4. + rng.gen_range(-2.0..4.0);
-                    }
-                    Item::Banana => {
-                        stats.quench += 10. + rng.gen_range(-1.2..1.8);
-                        stats.mood += 10. + rng.gen_range(-0.8..2.2);
-                        stats.drunk += 12. + rng.gen_range(-4.0..4.0);
-                    }
-                    Item::Cherry => {
-                        stats.quench += 5. + rng.gen_range(-1.2..1.8);
-                        stats.mood += 15. + rng.gen_range(-4.2..6.8);
-                        stats.drunk += 17. + rng.gen_range(-4.0..4.0);
-                    }
-                    _ => {}
-                }
-            }
+            let beverage = recipes.mix(&self.contains, rng);
+            println!("Mixed {}", beverage.name);
 
             self.contains.clear();
-            self.result = Some(Item::Beverage(Beverage { stats }));
+            self.result = Some(Item::Beverage(beverage));
             true
         } else {
             false
@@ -299,7 +327,7 @@ impl Container {
     }
 }
 
-#[derive(Component, Clone)]
+#[derive(Component, Deserialize, Clone)]
 pub enum Item {
     Orange,
     Banana,
@@ -307,15 +335,21 @@ pub enum Item {
     Beverage(Beverage),
 }
 
-#[derive(Clone)]
+#[derive(Deserialize, Clone)]
 pub struct Beverage {
+    pub name: String,
     pub stats: Stats,
+    /// Sprite from the recipe this was mixed from.
+    #[serde(skip)]
+    pub texture: Option<Handle<Image>>,
 }
 
 impl Default for Beverage {
     fn default() -> Self {
         Self {
+            name: "Beverage".to_string(),
             stats: Stats::default(),
+            texture: None,
         }
     }
 }
@@ -326,10 +360,18 @@ impl Item {
             Item::Orange => texture_assets.orange.clone(),
             Item::Banana => texture_assets.banana.clone(),
             Item::Cherry => texture_assets.cherry.clone(),
-            Item::Beverage(_) => texture_assets.beverage.clone(),
+            Item::Beverage(beverage) => beverage
+                .texture
+                .clone()
+                .unwrap_or_else(|| texture_assets.beverage.clone()),
         }
     }
 
+    /// Whether both are the same kind of item, ignoring any beverage details.
+    pub fn same_kind(&self, other: &Item) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other)
+    }
+
     pub fn spawn(
         self,
         translation: Vec3,
@@ -359,6 +401,7 @@ impl Item {
                     ..default()
                 },
                 self,
+                YSort::ON_TOP,
             ))
             .id();
         item_id
@@ -369,74 +412,145 @@ impl Item {
 /// Player logic is only active during the State `GameState::Playing`
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system(spawn_player.in_schedule(OnEnter(GameState::Playing)))
-            .add_system(move_player.in_set(OnUpdate(GameState::Playing)))
-            .add_systems(
-                (
-                    handle_actions,
-                    player_pickup,
-                    player_interact,
-                    position_held.after(player_pickup),
-                )
-                    .in_set(OnUpdate(GameState::Playing)),
-            );
+        app.add_system(
+            spawn_player
+                .after(SpawnWorld)
+                .run_if(starting_session)
+                .in_schedule(OnEnter(GameState::Playing)),
+        )
+        .add_systems(
+            (
+                handle_actions.after(InputSources),
+                move_player.after(handle_actions),
+                player_pickup.after(handle_actions),
+                player_interact.after(handle_actions),
+                position_held.after(player_pickup),
+                feed_animation_state_machines
+                    .after(move_player)
+                    .after(player_pickup)
+                    .after(player_interact)
+                    .before(Animating),
+            )
+                .in_set(OnUpdate(GameState::Playing)),
+        );
+    }
+}
+
+/// Who is behind each bartender this session.
+#[derive(SystemParam)]
+struct BartenderInputs<'w> {
+    play_mode: Res<'w, PlayMode>,
+    gamepad: Res<'w, ActiveGamepad>,
+    replay: Option<Res<'w, Replay>>,
+}
+
+impl BartenderInputs<'_> {
+    /// One for each bartender, in order. A replay stands in for everyone at the controls.
+    fn sources(&self) -> Vec<InputSource> {
+        self.play_mode
+            .control_schemes(&self.gamepad)
+            .into_iter()
+            .map(|scheme| match self.replay {
+                Some(_) => InputSource::Replay,
+                None => InputSource::Controls(scheme),
+            })
+            .collect()
     }
 }
 
-fn spawn_player(mut commands: Commands, textures: Res<TextureAssets>) {
-    // Swag a position for the player and NPC, based on knowing the tile map origin of -400,-300
-    // Player at 12, 14
-    let position = Vec3::new(-400. + (12. * 32.), -300. + (14. * 32.), 18. - 14. + 0.5);
-    commands.spawn((
-        SpriteSheetBundle {
-            texture_atlas: textures.luna.clone(),
-            sprite: TextureAtlasSprite {
-                index: 0,
-                anchor: bevy::sprite::Anchor::BottomCenter,
-                ..default()
+fn spawn_player(
+    mut commands: Commands,
+    sheet_assets: Res<SheetAssets>,
+    sheets: Res<Assets<SpriteSheet>>,
+    level_assets: Res<LevelAssets>,
+    levels: Res<Assets<Level>>,
+    inputs: BartenderInputs,
+    tile_map_query: Query<(&TileMap, &Transform)>,
+) {
+    let (tile_map, tile_map_transform) = tile_map_query.single();
+    let level = levels
+        .get(&level_assets.bar)
+        .expect("Level is loaded before entering Playing");
+
+    let spawn_points = level.spawn_points(SpawnPoint::Player);
+    let first = spawn_points.first().copied().unwrap_or(IVec2::new(12, 14));
+    let skins = [sheet_assets.luna.clone(), sheet_assets.bartender2.clone()];
+    for (index, (source, sheet)) in inputs.sources().into_iter().zip(skins).enumerate() {
+        // Without a spawn point of their own, bartenders line up to the right of the first.
+        let tile = spawn_points
+            .get(index)
+            .copied()
+            .unwrap_or(first + IVec2::new(index as i32, 0));
+        let position = tile_map.tile_to_world(tile_map_transform.translation, tile);
+        let texture_atlas = sheets
+            .get(&sheet)
+            .map(|sheet| sheet.atlas.clone())
+            .unwrap_or_default();
+        commands.spawn((
+            SpriteSheetBundle {
+                texture_atlas,
+                sprite: TextureAtlasSprite {
+                    index: 0,
+                    anchor: bevy::sprite::Anchor::BottomCenter,
+                    ..default()
+                },
+                transform: Transform::from_translation(position).with_scale(SCALE),
+                ..Default::default()
             },
-            transform: Transform::from_translation(position).with_scale(SCALE),
-            ..Default::default()
-        },
-        Player::default(),
-        UserControllable,
-    ));
-
-    // NPCs at (12, 6), (8, 6), (2, 5), (17, 7)
-    spawn_npc(&mut commands, &textures, IVec2::new(12, 6));
-    spawn_npc(&mut commands, &textures, IVec2::new(8, 6));
-    spawn_npc(&mut commands, &textures, IVec2::new(2, 5));
-    spawn_npc(&mut commands, &textures, IVec2::new(17, 7));
+            Player::default(),
+            sheet,
+            UserControllable { player: index },
+            Actions::default(),
+            source,
+            AnimationComponent::default(),
+            AnimationStateMachine::default(),
+            YSort::STANDING,
+            SessionScoped,
+        ));
+    }
 }
 
-fn spawn_npc(commands: &mut Commands, textures: &Res<TextureAssets>, tile: IVec2) {
-    let position = Vec3::new(
-        -400. + (tile.x as f32 * 32.),
-        -300. + (tile.y as f32 * 32.),
-        18. - tile.y as f32 + 0.5,
-    );
-
-    commands.spawn((
-        SpriteSheetBundle {
-            texture_atlas: textures.npc1.clone(),
-            sprite: TextureAtlasSprite {
-                index: 0,
-                anchor: bevy::sprite::Anchor::BottomCenter,
-                ..default()
+pub fn spawn_npc(
+    commands: &mut Commands,
+    sheet_assets: &SheetAssets,
+    sheets: &Assets<SpriteSheet>,
+    position: Vec3,
+    rng: &mut impl Rng,
+) -> Entity {
+    let sheet = sheet_assets.npc1.clone();
+    let texture_atlas = sheets
+        .get(&sheet)
+        .map(|sheet| sheet.atlas.clone())
+        .unwrap_or_default();
+    commands
+        .spawn((
+            SpriteSheetBundle {
+                texture_atlas,
+                sprite: TextureAtlasSprite {
+                    index: 0,
+                    anchor: bevy::sprite::Anchor::BottomCenter,
+                    ..default()
+                },
+                transform: Transform::from_translation(position).with_scale(SCALE),
+                ..Default::default()
             },
-            transform: Transform::from_translation(position).with_scale(SCALE),
-            ..Default::default()
-        },
-        Player::default(),
-        NPC::default(),
-        AnimationComponent::default(),
-    ));
+            sheet,
+            Player::default(),
+            NPC::new(rng),
+            Actions::default(),
+            InputSource::Ai,
+            AnimationComponent::default(),
+            AnimationStateMachine::default(),
+            YSort::STANDING,
+            SessionScoped,
+        ))
+        .id()
 }
 
 fn move_player(
     time: Res<Time>,
     mut player_query: Query<
-        (&mut Transform, &mut TextureAtlasSprite, &mut Player),
+        (&mut Transform, &mut Player),
         (Without<Tile>, Without<Item>, Without<TileMap>),
     >,
     tile_map_query: Query<(&TileMap, &Transform), (With<TileMap>, Without<Player>)>,
@@ -444,60 +558,62 @@ fn move_player(
 ) {
     let (tile_map, tile_map_transform) = tile_map_query.single();
 
-    for (mut player_transform, mut sprite, mut player) in &mut player_query {
+    for (mut player_transform, mut player) in &mut player_query {
         let Some(player_movement) = player.movement else {
+            if player.speed != 0. {
+                player.speed = 0.;
+            }
             continue;
         };
-        let speed = 150.;
         let movement = Vec3::new(
-            player_movement.x * speed * time.delta_seconds(),
-            player_movement.y * speed * time.delta_seconds(),
+            player_movement.x * WALK_SPEED * time.delta_seconds(),
+            player_movement.y * WALK_SPEED * time.delta_seconds(),
             0.,
         );
 
         player.heading = PlayerHeading::from_vec(movement.truncate());
-        sprite.index = player.heading.sprite_index();
 
         let new_translation = player_transform.translation + movement;
-        let new_tile = tile_map
-            .to_tile(tile_map.to_tile_space(tile_map_transform.translation, new_translation));
-        if new_tile
-            == tile_map.to_tile(
-                tile_map
-                    .to_tile_space(tile_map_transform.translation, player_transform.translation),
-            )
-        {
+        let new_tile = tile_map.world_to_tile(tile_map_transform.translation, new_translation);
+        let passable = new_tile
+            == tile_map.world_to_tile(tile_map_transform.translation, player_transform.translation)
+            || tile_map
+                .tile_at(new_tile)
+                .and_then(|tile_entity| tile_query.get(tile_entity).ok())
+                .is_some_and(|(tile, _)| matches!(tile.passable(), Passable::Passable));
+        if passable {
             player_transform.translation += movement;
-            continue;
+            player.speed = player_movement.length() * WALK_SPEED;
+        } else {
+            player.speed = 0.;
         }
+    }
+}
 
-        if let Some(tile_entity) = tile_map.tile_at(new_tile) {
-            if let Ok((tile, _transform)) = tile_query.get(tile_entity) {
-                if let Passable::Passable = tile.passable() {
-                    player_transform.translation += movement;
-                    player_transform.translation.z = tile_map.tile_z(&new_tile) + 0.5;
-                }
-            }
-        }
+/// Walking and carrying, for players and NPCs alike. Players are written to every frame, so
+/// this doesn't bother looking for changes.
+fn feed_animation_state_machines(mut query: Query<(&Player, &mut AnimationStateMachine)>) {
+    for (player, mut machine) in &mut query {
+        machine.set_movement(player.heading, player.speed);
+        machine.set_carrying(player.holding.is_some());
     }
 }
 
-fn handle_actions(
-    actions: Res<Actions>,
-    mut player_query: Query<&mut Player, With<UserControllable>>,
-) {
-    let mut player = player_query.single_mut();
-    player.pickup_action = actions.pick_up.0 == true && actions.pick_up.1 == false;
-    player.interact_action = actions.interact.0 == true && actions.interact.1 == false;
+/// Turns what players and NPCs are trying to do into what they do. Both come through here.
+fn handle_actions(mut player_query: Query<(&Actions, &mut Player)>) {
+    for (actions, mut player) in &mut player_query {
+        player.pickup_action = actions.pick_up.just_pressed();
+        player.interact_action = actions.interact.just_pressed();
 
-    player.movement = actions.player_movement;
+        player.movement = actions.movement;
+    }
 }
 
 fn player_pickup(
     mut commands: Commands,
     textures: Res<TextureAssets>,
     mut player_query: Query<
-        (Entity, &Transform, &mut Player),
+        (Entity, &Transform, &mut Player, Option<&UserControllable>),
         (Without<TileMap>, Without<Interactable>, Without<Tile>),
     >,
     tile_map_query: Query<
@@ -512,14 +628,26 @@ fn player_pickup(
     item_query: Query<&Item>,
 ) {
     let (tile_map, tile_map_transform) = tile_map_query.single();
-    for (player_entity, player_transform, mut player) in &mut player_query {
+    // Where each bartender stands, to hand items between them.
+    let bartenders: Vec<(Entity, IVec2)> = player_query
+        .iter()
+        .filter(|(_, _, _, controllable)| controllable.is_some())
+        .map(|(entity, transform, _, _)| {
+            let tile =
+                tile_map.world_to_tile(tile_map_transform.translation, transform.translation);
+            (entity, tile)
+        })
+        .collect();
+    let mut handoffs = Vec::new();
+
+    for (player_entity, player_transform, mut player, controllable) in &mut player_query {
         if !player.pickup_action {
             continue;
         }
         player.pickup_action = false;
 
         let tile_index =
-            tile_map.camera_to_tile(tile_map_transform.translation, player_transform.translation);
+            tile_map.world_to_tile(tile_map_transform.translation, player_transform.translation);
 
         if let Some(holding) = player.holding {
             // Get the held item
@@ -547,6 +675,16 @@ fn player_pickup(
                     }
                 }
             }
+
+            // Nothing took it, so offer it to another bartender alongside or in front.
+            if player.holding.is_some() && controllable.is_some() {
+                let facing = tile_index + player.heading.as_offset();
+                if let Some((receiver, _)) = bartenders.iter().find(|(entity, tile)| {
+                    *entity != player_entity && (*tile == tile_index || *tile == facing)
+                }) {
+                    handoffs.push((player_entity, *receiver, holding));
+                }
+            }
         } else {
             // Try to pick up.
             for idx in [tile_index, tile_index + player.heading.as_offset()] {
@@ -574,6 +712,22 @@ fn player_pickup(
             }
         }
     }
+
+    // Handoffs wait until everyone has acted, as the receiver may have just picked something up
+    // or handed over their own item.
+    for (giver_entity, receiver_entity, item_entity) in handoffs {
+        let Ok([(_, _, mut giver, _), (_, _, mut receiver, _)]) =
+            player_query.get_many_mut([giver_entity, receiver_entity])
+        else {
+            continue;
+        };
+        if giver.holding != Some(item_entity) || receiver.holding.is_some() {
+            continue;
+        }
+        println!("Hand over");
+        giver.holding = None;
+        receiver.hold_item(receiver_entity, item_entity, &mut commands);
+    }
 }
 
 fn position_held(
@@ -600,11 +754,14 @@ fn position_held(
                 4. * offset.y as f32
             }
         };
-        transform.translation = Vec3::new(x, 16.0, 0.5);
+        transform.translation = Vec3::new(x, 16.0, 0.);
     }
 }
 
 fn player_interact(
+    mut rng: ResMut<GameRng>,
+    recipe_assets: Res<RecipeAssets>,
+    recipe_books: Res<Assets<RecipeBook>>,
     player_query: Query<
         (&Transform, &Player),
         (Without<TileMap>, Without<Interactable>, Without<Tile>),
@@ -619,6 +776,10 @@ fn player_interact(
     >,
     tile_query: Query<&Children, With<Tile>>,
 ) {
+    let Some(recipes) = recipe_books.get(&recipe_assets.book) else {
+        return;
+    };
+
     for (player_transform, player) in &player_query {
         if !player.interact_action {
             continue;
@@ -627,7 +788,7 @@ fn player_interact(
         let (tile_map, tile_map_transform) = tile_map_query.single();
 
         let tile_index =
-            tile_map.camera_to_tile(tile_map_transform.translation, player_transform.translation);
+            tile_map.world_to_tile(tile_map_transform.translation, player_transform.translation);
 
         for idx in [tile_index, tile_index + player.heading.as_offset()] {
             let Some(tile_entity) = tile_map.tile_at(idx) else {
@@ -643,7 +804,7 @@ fn player_interact(
                 continue;
             };
 
-                if interactable.interact() {
+                if interactable.interact(recipes, &mut *rng) {
                     println!("Interact successful");
                     break;
                 }
@@ -651,3 +812,67 @@ fn player_interact(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{Act, TestApp};
+    use bevy::utils::HashSet;
+
+    #[test]
+    fn mixing_orange_and_banana_makes_a_beverage() {
+        let mut test = TestApp::new(&["BoBbB", ".P...", "=m==="]);
+        let luna = test.bartender(0);
+        let mixer_tile = IVec2::new(1, 0);
+
+        // An orange from behind, into the mixer in front.
+        test.face(luna, Vec2::Y);
+        test.pick_up(luna);
+        test.face(luna, Vec2::NEG_Y);
+        test.pick_up(luna);
+
+        // A banana from along the bar, into the mixer too.
+        test.walk_to(luna, IVec2::new(3, 1));
+        test.face(luna, Vec2::Y);
+        test.pick_up(luna);
+        test.walk_to(luna, IVec2::new(1, 1));
+        test.face(luna, Vec2::NEG_Y);
+        test.pick_up(luna);
+
+        let Interactable::Mixer(mixer) = test.interactable_at(mixer_tile) else {
+            panic!("No mixer");
+        };
+        assert_eq!(mixer.contains.len(), 2);
+        assert!(mixer.result.is_none());
+
+        test.interact(luna);
+
+        let Interactable::Mixer(mixer) = test.interactable_at(mixer_tile) else {
+            panic!("No mixer");
+        };
+        assert!(mixer.contains.is_empty());
+        let Some(Item::Beverage(beverage)) = &mixer.result else {
+            panic!("The mixer doesn't hold a beverage");
+        };
+        assert_eq!(beverage.name, "Tropical Punch");
+    }
+
+    #[test]
+    fn walking_steps_through_frames_and_stopping_stands_still() {
+        let mut test = TestApp::new(&[".....", ".P...", "....."]);
+        let luna = test.bartender(0);
+        let sprite_index =
+            |test: &mut TestApp| test.world().get::<TextureAtlasSprite>(luna).unwrap().index;
+
+        let mut shown = HashSet::new();
+        for _ in 0..30 {
+            test.act(luna, Act::Walk(Vec2::X));
+            shown.insert(sprite_index(&mut test));
+        }
+        // Each step, and standing between them.
+        assert_eq!(shown, HashSet::from_iter([10, 3, 11]));
+
+        test.act(luna, Act::Idle);
+        assert_eq!(sprite_index(&mut test), 3);
+    }
+}
@@ -0,0 +1,122 @@
+use crate::npc::Stats;
+use crate::player::{Beverage, Item};
+use bevy::asset::{AssetLoader, AssetPath, LoadContext, LoadedAsset};
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use bevy::utils::BoxedFuture;
+use rand::prelude::*;
+use serde::Deserialize;
+
+/// All the drinks that can be mixed, loaded from a `.recipes.ron` file.
+#[derive(Deserialize, TypeUuid)]
+#[uuid = "b6f1e2a4-3c7d-4b8e-9f21-6d5a0c4e7b13"]
+pub struct RecipeBook {
+    pub recipes: Vec<Recipe>,
+    /// Served when the mixer contents don't match any recipe.
+    pub fallback: Recipe,
+}
+
+#[derive(Deserialize)]
+pub struct Recipe {
+    pub name: String,
+    /// Items that go into the mixer, in any order.
+    #[serde(default)]
+    pub ingredients: Vec<Item>,
+    pub stats: Stats,
+    /// Path of the drink sprite, relative to the assets folder.
+    pub sprite: String,
+    #[serde(skip)]
+    pub texture: Handle<Image>,
+}
+
+impl Recipe {
+    fn matches(&self, items: &[Item]) -> bool {
+        if self.ingredients.len() != items.len() {
+            return false;
+        }
+        let mut remaining: Vec<&Item> = items.iter().collect();
+        for ingredient in &self.ingredients {
+            let Some(idx) = remaining.iter().position(|item| item.same_kind(ingredient)) else {
+                return false;
+            };
+            remaining.swap_remove(idx);
+        }
+        true
+    }
+
+    pub fn beverage(&self) -> Beverage {
+        Beverage {
+            name: self.name.clone(),
+            stats: self.stats,
+            texture: Some(self.texture.clone()),
+        }
+    }
+}
+
+impl RecipeBook {
+    pub fn find(&self, items: &[Item]) -> Option<&Recipe> {
+        self.recipes.iter().find(|recipe| recipe.matches(items))
+    }
+
+    /// Mix the passed items into a drink.
+    ///
+    /// Unknown combinations make the fallback drink, with stats blended from each item.
+    pub fn mix(&self, items: &[Item], rng: &mut impl Rng) -> Beverage {
+        if let Some(recipe) = self.find(items) {
+            return recipe.beverage();
+        }
+
+        let mut stats = self.fallback.stats;
+        for item in items {
+            match item {
+                Item::Orange => {
+                    stats.quench += 20. + rng.gen_range(-4.0..4.0);
+                    stats.mood += 5. + rng.gen_range(-1.2..1.8);
+                    stats.drunk += 4. + rng.gen_range(-2.0..4.0);
+                }
+                Item::Banana => {
+                    stats.quench += 10. + rng.gen_range(-1.2..1.8);
+                    stats.mood += 10. + rng.gen_range(-0.8..2.2);
+                    stats.drunk += 12. + rng.gen_range(-4.0..4.0);
+                }
+                Item::Cherry => {
+                    stats.quench += 5. + rng.gen_range(-1.2..1.8);
+                    stats.mood += 15. + rng.gen_range(-4.2..6.8);
+                    stats.drunk += 17. + rng.gen_range(-4.0..4.0);
+                }
+                _ => {}
+            }
+        }
+        Beverage {
+            stats,
+            ..self.fallback.beverage()
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct RecipeBookLoader;
+
+impl AssetLoader for RecipeBookLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let mut book = ron::de::from_bytes::<RecipeBook>(bytes)?;
+            let mut dependencies = Vec::new();
+            for recipe in book.recipes.iter_mut().chain([&mut book.fallback]) {
+                let path = AssetPath::from(recipe.sprite.as_str()).to_owned();
+                recipe.texture = load_context.get_handle(path.clone());
+                dependencies.push(path);
+            }
+            load_context.set_default_asset(LoadedAsset::new(book).with_dependencies(dependencies));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["recipes.ron"]
+    }
+}
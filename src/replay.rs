@@ -0,0 +1,255 @@
+use crate::actions::{Actions, ActiveGamepad, InputSource, InputSources, PlayMode};
+use crate::options::LaunchOptions;
+use crate::player::UserControllable;
+use crate::rng::{seed_session, GameRng, SeedChoice};
+use crate::storage;
+use crate::{starting_session, GameState};
+use bevy::app::AppExit;
+use bevy::ecs::schedule::ExecutorKind;
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::Duration;
+
+pub struct ReplayPlugin;
+
+/// This plugin records sessions with `--record <file>`, and plays them back with
+/// `--replay <file>`. A recording is the session's seed and, for every frame, how long it took
+/// and what each bartender did. Replaying feeds both back in, so the session plays out again
+/// exactly, bugs and all.
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        let options = app
+            .world
+            .get_resource::<LaunchOptions>()
+            .cloned()
+            .unwrap_or_default();
+
+        if options.deterministic() {
+            // The multi-threaded executor may run unordered systems in a different order each
+            // frame, and the order they draw random numbers in with it.
+            app.edit_schedule(CoreSchedule::Main, |schedule| {
+                schedule.set_executor_kind(ExecutorKind::SingleThreaded);
+            })
+            .add_startup_system(run_single_threaded);
+        }
+
+        if let Some(path) = options.record {
+            app.insert_resource(Recorder {
+                path,
+                recording: None,
+            })
+            .add_system(
+                start_recording
+                    .after(seed_session)
+                    .run_if(starting_session)
+                    .in_schedule(OnEnter(GameState::Playing)),
+            )
+            .add_system(
+                record_frame
+                    .after(InputSources)
+                    .in_set(OnUpdate(GameState::Playing)),
+            )
+            .add_system(save_recording.in_schedule(OnEnter(GameState::Paused)))
+            .add_system(save_recording.in_schedule(OnEnter(GameState::Results)))
+            .add_system(
+                save_recording
+                    .run_if(on_event::<AppExit>())
+                    .in_base_set(CoreSet::Last),
+            );
+        }
+
+        if let Some(path) = options.replay {
+            let Some(recording) = storage::load_from::<Recording>(&path) else {
+                println!("Couldn't read a recording from {}", path.display());
+                return;
+            };
+            app.insert_resource(Replay {
+                recording,
+                frame: 0,
+            })
+            .add_system(
+                start_replay
+                    .run_if(resource_exists::<Replay>())
+                    .in_schedule(OnEnter(GameState::Menu)),
+            )
+            .add_system(
+                replay_actions
+                    .run_if(resource_exists::<Replay>())
+                    .in_set(InputSources)
+                    .in_set(OnUpdate(GameState::Playing)),
+            )
+            .add_system(
+                step_replay_time
+                    .run_if(resource_exists::<Replay>())
+                    .in_base_set(CoreSet::Last),
+            );
+        }
+    }
+}
+
+/// A recorded session.
+#[derive(Serialize, Deserialize)]
+pub struct Recording {
+    pub seed: u64,
+    pub play_mode: PlayMode,
+    pub frames: Vec<Frame>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Frame {
+    pub delta: Duration,
+    /// One for each bartender, in the order they were spawned.
+    pub actions: Vec<RecordedActions>,
+}
+
+/// The `Actions` of a bartender in one frame.
+#[derive(Serialize, Deserialize)]
+pub struct RecordedActions {
+    pub movement: Option<Vec2>,
+    pub pick_up: bool,
+    pub interact: bool,
+}
+
+/// The session being recorded, and where to save it.
+#[derive(Resource)]
+struct Recorder {
+    path: PathBuf,
+    recording: Option<Recording>,
+}
+
+/// The recording being played back, and the frame it is up to.
+#[derive(Resource)]
+pub struct Replay {
+    recording: Recording,
+    frame: usize,
+}
+
+/// Makes the schedules other than the main one, like the `OnEnter` ones, single threaded too.
+/// The main one is busy running this, so isn't in `Schedules`.
+fn run_single_threaded(mut schedules: ResMut<Schedules>) {
+    for (_, schedule) in schedules.iter_mut() {
+        schedule.set_executor_kind(ExecutorKind::SingleThreaded);
+    }
+}
+
+fn start_recording(mut recorder: ResMut<Recorder>, rng: Res<GameRng>, play_mode: Res<PlayMode>) {
+    println!("Recording session with seed {}", rng.seed());
+    recorder.recording = Some(Recording {
+        seed: rng.seed(),
+        play_mode: *play_mode,
+        frames: Vec::new(),
+    });
+}
+
+fn record_frame(
+    time: Res<Time>,
+    mut recorder: ResMut<Recorder>,
+    player_query: Query<(&UserControllable, &Actions)>,
+) {
+    let Some(recording) = recorder.recording.as_mut() else {
+        return;
+    };
+    let mut players: Vec<_> = player_query.iter().collect();
+    players.sort_by_key(|(controllable, _)| controllable.player);
+    recording.frames.push(Frame {
+        delta: time.delta(),
+        actions: players
+            .into_iter()
+            .map(|(_, actions)| RecordedActions {
+                movement: actions.movement,
+                pick_up: actions.pick_up.pressed(),
+                interact: actions.interact.pressed(),
+            })
+            .collect(),
+    });
+}
+
+fn save_recording(recorder: Res<Recorder>) {
+    if let Some(recording) = &recorder.recording {
+        storage::save_to(&recorder.path, recording);
+        println!(
+            "Saved {} frames to {}",
+            recording.frames.len(),
+            recorder.path.display()
+        );
+    }
+}
+
+/// Skips the menu, straight into the recorded session. Back at the menu afterwards, the replay
+/// is over.
+fn start_replay(
+    mut commands: Commands,
+    replay: Res<Replay>,
+    options: Res<LaunchOptions>,
+    mut seed_choice: ResMut<SeedChoice>,
+    mut play_mode: ResMut<PlayMode>,
+    mut strategy: ResMut<TimeUpdateStrategy>,
+    mut state: ResMut<NextState<GameState>>,
+) {
+    if replay.frame > 0 {
+        commands.remove_resource::<Replay>();
+        *seed_choice = SeedChoice::from_options(&options);
+        *strategy = TimeUpdateStrategy::Automatic;
+        return;
+    }
+    println!("Replaying session with seed {}", replay.recording.seed);
+    *seed_choice = SeedChoice::Fixed(replay.recording.seed);
+    *play_mode = replay.recording.play_mode;
+    state.set(GameState::Playing);
+}
+
+/// Fills in the bartenders' `Actions` from the recording. When it runs out, the players take
+/// over from where it ended.
+fn replay_actions(
+    mut commands: Commands,
+    mut replay: ResMut<Replay>,
+    play_mode: Res<PlayMode>,
+    gamepad: Res<ActiveGamepad>,
+    mut strategy: ResMut<TimeUpdateStrategy>,
+    mut player_query: Query<(&UserControllable, &mut InputSource, &mut Actions)>,
+) {
+    let Some(frame) = replay.recording.frames.get(replay.frame) else {
+        println!("Replay finished after {} frames", replay.frame);
+        let schemes = play_mode.control_schemes(&gamepad);
+        for (controllable, mut source, _) in &mut player_query {
+            if let Some(scheme) = schemes.get(controllable.player) {
+                *source = InputSource::Controls(*scheme);
+            }
+        }
+        commands.remove_resource::<Replay>();
+        *strategy = TimeUpdateStrategy::Automatic;
+        return;
+    };
+    for (controllable, source, mut actions) in &mut player_query {
+        let (InputSource::Replay, Some(recorded)) =
+            (*source, frame.actions.get(controllable.player))
+        else {
+            continue;
+        };
+        actions.movement = recorded.movement;
+        actions.pick_up.update(recorded.pick_up);
+        actions.interact.update(recorded.interact);
+    }
+    replay.frame += 1;
+}
+
+/// Time only moves on by the recorded frame lengths while the session is playing. It is set at
+/// the end of a frame, for the next one, so it needs to look ahead at the state that will be.
+fn step_replay_time(
+    replay: Res<Replay>,
+    time: Res<Time>,
+    state: Res<State<GameState>>,
+    next_state: Res<NextState<GameState>>,
+    mut strategy: ResMut<TimeUpdateStrategy>,
+) {
+    let playing = next_state.0.as_ref().unwrap_or(&state.0) == &GameState::Playing;
+    match (playing, replay.recording.frames.get(replay.frame)) {
+        (true, Some(frame)) => {
+            let last_update = time.last_update().unwrap_or_else(|| time.startup());
+            *strategy = TimeUpdateStrategy::ManualInstant(last_update + frame.delta);
+        }
+        _ => *strategy = TimeUpdateStrategy::Automatic,
+    }
+}
@@ -0,0 +1,137 @@
+use crate::loading::FontAssets;
+use crate::menu::{ButtonColors, ButtonInteractions};
+use crate::score::{Score, ScoreEvent};
+use crate::{GameState, Session};
+use bevy::prelude::*;
+
+pub struct ResultsPlugin;
+
+/// This plugin shows the summary of a shift once it is over, drawn over the frozen world.
+impl Plugin for ResultsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(setup_results.in_schedule(OnEnter(GameState::Results)))
+            .add_system(click_results_buttons.in_set(OnUpdate(GameState::Results)))
+            .add_system(cleanup_results.in_schedule(OnExit(GameState::Results)));
+    }
+}
+
+#[derive(Component)]
+struct ResultsScreen;
+
+#[derive(Component)]
+enum ResultsButton {
+    PlayAgain,
+    Menu,
+}
+
+impl ResultsButton {
+    fn label(&self) -> &'static str {
+        match self {
+            ResultsButton::PlayAgain => "Play again",
+            ResultsButton::Menu => "Menu",
+        }
+    }
+}
+
+fn setup_results(
+    mut commands: Commands,
+    font_assets: Res<FontAssets>,
+    button_colors: Res<ButtonColors>,
+    score: Res<Score>,
+) {
+    let text_style = |font_size| TextStyle {
+        font: font_assets.fira_sans.clone(),
+        font_size,
+        color: Color::rgb(0.9, 0.9, 0.9),
+    };
+    let lines = [
+        format!(
+            "Drinks served: {} ({} as ordered)",
+            score.drinks_served(),
+            score.count(ScoreEvent::RightDrink)
+        ),
+        format!("Fruit served: {}", score.count(ScoreEvent::RawFruit)),
+        format!("Dances: {}", score.count(ScoreEvent::Dance)),
+        format!("Chats: {}", score.count(ScoreEvent::Chat)),
+        format!("Tears: {}", score.count(ScoreEvent::Cry)),
+        format!("Fights: {}", score.count(ScoreEvent::Fight)),
+        format!("Pukes: {}", score.count(ScoreEvent::Puke)),
+        format!("Walk-outs: {}", score.count(ScoreEvent::WalkOut)),
+    ];
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                    position_type: PositionType::Absolute,
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..Default::default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.8).into(),
+                ..Default::default()
+            },
+            ResultsScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section("Shift over", text_style(60.0)));
+            for line in lines {
+                parent.spawn(TextBundle::from_section(line, text_style(28.0)));
+            }
+            parent.spawn(TextBundle::from_section(
+                format!("Total: {}", score.total()),
+                text_style(40.0),
+            ));
+            for button in [ResultsButton::PlayAgain, ResultsButton::Menu] {
+                parent
+                    .spawn(ButtonBundle {
+                        style: Style {
+                            size: Size::new(Val::Px(200.0), Val::Px(50.0)),
+                            margin: UiRect::all(Val::Px(8.0)),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..Default::default()
+                        },
+                        background_color: button_colors.normal.into(),
+                        ..Default::default()
+                    })
+                    .with_children(|parent| {
+                        parent.spawn(TextBundle::from_section(button.label(), text_style(40.0)));
+                    })
+                    .insert(button);
+            }
+        });
+}
+
+fn click_results_buttons(
+    mut commands: Commands,
+    button_colors: Res<ButtonColors>,
+    mut state: ResMut<NextState<GameState>>,
+    mut interaction_query: ButtonInteractions<ResultsButton>,
+) {
+    for (interaction, mut color, button) in &mut interaction_query {
+        match *interaction {
+            Interaction::Clicked => {
+                commands.remove_resource::<Session>();
+                match button {
+                    ResultsButton::PlayAgain => state.set(GameState::Playing),
+                    ResultsButton::Menu => state.set(GameState::Menu),
+                }
+            }
+            Interaction::Hovered => {
+                *color = button_colors.hovered.into();
+            }
+            Interaction::None => {
+                *color = button_colors.normal.into();
+            }
+        }
+    }
+}
+
+fn cleanup_results(mut commands: Commands, screen: Query<Entity, With<ResultsScreen>>) {
+    for entity in &screen {
+        commands.entity(entity).despawn_recursive();
+    }
+}
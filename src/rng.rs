@@ -0,0 +1,105 @@
+use crate::options::LaunchOptions;
+use crate::{starting_session, GameState};
+use bevy::prelude::*;
+use chrono::{Datelike, Local};
+use rand::rngs::StdRng;
+use rand::{thread_rng, Error, Rng, RngCore, SeedableRng};
+
+pub struct RngPlugin;
+
+/// This plugin provides the random number generator used by all gameplay, reseeded at the start
+/// of each session so that a session can be played again exactly from its seed.
+impl Plugin for RngPlugin {
+    fn build(&self, app: &mut App) {
+        let options = app
+            .world
+            .get_resource::<LaunchOptions>()
+            .cloned()
+            .unwrap_or_default();
+        app.insert_resource(SeedChoice::from_options(&options))
+            .insert_resource(GameRng::new(0))
+            .add_system(
+                seed_session
+                    .run_if(starting_session)
+                    .in_schedule(OnEnter(GameState::Playing)),
+            );
+    }
+}
+
+/// How the next sessions are seeded.
+#[derive(Resource, Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SeedChoice {
+    /// A new seed each session.
+    #[default]
+    Random,
+    Fixed(u64),
+    /// The date, like 20230415, so a day's sessions are all the same.
+    Daily,
+}
+
+impl SeedChoice {
+    pub fn from_options(options: &LaunchOptions) -> Self {
+        match (options.seed, options.daily) {
+            (Some(seed), _) => SeedChoice::Fixed(seed),
+            (None, true) => SeedChoice::Daily,
+            (None, false) => SeedChoice::Random,
+        }
+    }
+
+    fn seed(&self) -> u64 {
+        match self {
+            SeedChoice::Random => thread_rng().gen(),
+            SeedChoice::Fixed(seed) => *seed,
+            SeedChoice::Daily => {
+                let today = Local::now().date_naive();
+                today.year() as u64 * 10000 + today.month() as u64 * 100 + today.day() as u64
+            }
+        }
+    }
+}
+
+/// Source of all gameplay randomness. Use it wherever `thread_rng()` would be used.
+#[derive(Resource)]
+pub struct GameRng {
+    seed: u64,
+    rng: StdRng,
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        GameRng {
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// The seed of the current session.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+impl RngCore for GameRng {
+    fn next_u32(&mut self) -> u32 {
+        self.rng.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.rng.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.rng.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.rng.try_fill_bytes(dest)
+    }
+}
+
+pub fn seed_session(choice: Res<SeedChoice>, mut rng: ResMut<GameRng>) {
+    let seed = choice.seed();
+    // Logged, so a session can be played again with `--seed`.
+    println!("Session seed {} ({:?})", seed, *choice);
+    *rng = GameRng::new(seed);
+}
@@ -1,12 +1,70 @@
 use crate::loading::FontAssets;
-use crate::GameState;
+use crate::shift::Shift;
+use crate::tween::{Ease, Tween};
+use crate::{starting_session, GameState, SessionScoped};
 use bevy::prelude::*;
 
 pub struct ScorePlugin;
 
+/// Popups start over the head of whoever scored.
+const POPUP_HEIGHT: f32 = 72.;
+/// How far popups float up as they fade out.
+const POPUP_RISE: f32 = 24.;
+/// In front of everything in the bar.
+const POPUP_Z: f32 = 100.;
+
+/// Something that happened during a shift that counts towards the score.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ScoreEvent {
+    Chat,
+    Dance,
+    Cry,
+    Fight,
+    Puke,
+    /// Served the drink that was ordered.
+    RightDrink,
+    /// Served a drink, but not the one that was ordered.
+    WrongDrink,
+    /// Served a fruit instead of a drink.
+    RawFruit,
+    /// Gave up waiting for an order.
+    WalkOut,
+}
+
+impl ScoreEvent {
+    pub const ALL: [ScoreEvent; 9] = [
+        ScoreEvent::Chat,
+        ScoreEvent::Dance,
+        ScoreEvent::Cry,
+        ScoreEvent::Fight,
+        ScoreEvent::Puke,
+        ScoreEvent::RightDrink,
+        ScoreEvent::WrongDrink,
+        ScoreEvent::RawFruit,
+        ScoreEvent::WalkOut,
+    ];
+
+    pub fn points(&self) -> i32 {
+        match self {
+            ScoreEvent::Chat => 10,
+            ScoreEvent::Dance => 50,
+            ScoreEvent::Cry => -10,
+            ScoreEvent::Fight => -20,
+            ScoreEvent::Puke => -50,
+            ScoreEvent::RightDrink => 30,
+            ScoreEvent::WrongDrink => 5,
+            ScoreEvent::RawFruit => -15,
+            ScoreEvent::WalkOut => -25,
+        }
+    }
+}
+
+/// Log of everything scored this shift.
 #[derive(Resource)]
 pub struct Score {
-    pub value: i32,
+    events: Vec<ScoreEvent>,
+    /// Scored since popups were last shown, and by whom.
+    popups: Vec<(ScoreEvent, Entity)>,
 }
 
 #[derive(Component)]
@@ -14,19 +72,51 @@ struct ScoreBoard;
 
 impl Default for Score {
     fn default() -> Self {
-        Score { value: 0 }
+        Score {
+            events: Vec::new(),
+            popups: Vec::new(),
+        }
+    }
+}
+
+impl Score {
+    /// Logs `event`, and pops its points up over `by`.
+    pub fn record(&mut self, event: ScoreEvent, by: Entity) {
+        self.events.push(event);
+        self.popups.push((event, by));
+    }
+
+    pub fn total(&self) -> i32 {
+        self.events.iter().map(|event| event.points()).sum()
+    }
+
+    pub fn count(&self, event: ScoreEvent) -> usize {
+        self.events
+            .iter()
+            .filter(|logged| **logged == event)
+            .count()
+    }
+
+    /// Drinks served, right or wrong. Fruit doesn't count.
+    pub fn drinks_served(&self) -> usize {
+        self.count(ScoreEvent::RightDrink) + self.count(ScoreEvent::WrongDrink)
     }
 }
 
 impl Plugin for ScorePlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<Score>()
-            .add_system(setup_score.in_schedule(OnEnter(GameState::Playing)))
-            .add_system(update_score.in_set(OnUpdate(GameState::Playing)));
+            .add_system(
+                setup_score
+                    .run_if(starting_session)
+                    .in_schedule(OnEnter(GameState::Playing)),
+            )
+            .add_systems((update_score, show_score_popups).in_set(OnUpdate(GameState::Playing)));
     }
 }
 
 fn setup_score(mut commands: Commands, font_assets: Res<FontAssets>) {
+    commands.insert_resource(Score::default());
     commands.spawn((
         TextBundle::from_section(
             "Score: 0",
@@ -37,11 +127,62 @@ fn setup_score(mut commands: Commands, font_assets: Res<FontAssets>) {
             },
         ),
         ScoreBoard,
+        SessionScoped,
     ));
 }
 
-fn update_score(score: Res<Score>, mut query: Query<(&ScoreBoard, &mut Text)>) {
+fn update_score(score: Res<Score>, shift: Res<Shift>, mut query: Query<(&ScoreBoard, &mut Text)>) {
     let (_, mut text) = query.single_mut();
 
-    text.sections[0].value = format!("Score: {}", score.value);
+    let remaining = shift.remaining().ceil() as u32;
+    text.sections[0].value = format!(
+        "Score: {}  {}:{:02}",
+        score.total(),
+        remaining / 60,
+        remaining % 60
+    );
+}
+
+fn show_score_popups(
+    mut commands: Commands,
+    font_assets: Res<FontAssets>,
+    mut score: ResMut<Score>,
+    query: Query<&GlobalTransform>,
+) {
+    if score.popups.is_empty() {
+        return;
+    }
+
+    for (event, entity) in std::mem::take(&mut score.popups) {
+        // Whoever scored may have gone already.
+        let Ok(transform) = query.get(entity) else {
+            continue;
+        };
+        let points = event.points();
+        let color = if points < 0 {
+            Color::rgb(0.9, 0.3, 0.3)
+        } else {
+            Color::rgb(0.4, 0.9, 0.4)
+        };
+        let position = transform.translation().truncate() + Vec2::new(0., POPUP_HEIGHT);
+        commands.spawn((
+            Text2dBundle {
+                text: Text::from_section(
+                    format!("{:+}", points),
+                    TextStyle {
+                        font: font_assets.fira_sans.clone(),
+                        font_size: 20.,
+                        color,
+                    },
+                ),
+                transform: Transform::from_translation(position.extend(POPUP_Z)),
+                ..default()
+            },
+            Tween::new(1., Ease::QuadOut)
+                .move_to(position + Vec2::new(0., POPUP_RISE))
+                .fade_to(color.with_a(0.))
+                .on_complete(|commands, entity| commands.entity(entity).despawn()),
+            SessionScoped,
+        ));
+    }
 }
@@ -0,0 +1,320 @@
+use crate::actions::{GameControl, InputMap, KEY_SLOTS};
+use crate::loading::FontAssets;
+use crate::menu::{ButtonColors, ButtonInteractions};
+use crate::GameState;
+use bevy::prelude::*;
+
+pub struct SettingsPlugin;
+
+/// This plugin draws the controls screen, where each `GameControl` can be rebound. The bindings
+/// are saved when leaving the screen.
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Rebinding>()
+            .add_system(setup_settings.in_schedule(OnEnter(GameState::Settings)))
+            .add_systems(
+                (
+                    click_binding_slots,
+                    click_settings_buttons,
+                    capture_binding,
+                    update_slot_labels,
+                )
+                    .in_set(OnUpdate(GameState::Settings)),
+            )
+            .add_system(cleanup_settings.in_schedule(OnExit(GameState::Settings)));
+    }
+}
+
+#[derive(Component)]
+struct SettingsScreen;
+
+#[derive(Component)]
+struct SettingsMessage;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Slot {
+    Key(usize),
+    Button,
+}
+
+/// A button showing, and rebinding, one input of a control.
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+struct BindingSlot {
+    control: GameControl,
+    slot: Slot,
+}
+
+/// The text inside a `BindingSlot`.
+#[derive(Component)]
+struct SlotLabel(BindingSlot);
+
+#[derive(Component)]
+enum SettingsButton {
+    Defaults,
+    Back,
+}
+
+impl SettingsButton {
+    fn label(&self) -> &'static str {
+        match self {
+            SettingsButton::Defaults => "Defaults",
+            SettingsButton::Back => "Back",
+        }
+    }
+}
+
+/// The slot waiting for a key or button press, if any.
+#[derive(Resource, Default)]
+struct Rebinding(Option<BindingSlot>);
+
+fn setup_settings(
+    mut commands: Commands,
+    font_assets: Res<FontAssets>,
+    button_colors: Res<ButtonColors>,
+    mut rebinding: ResMut<Rebinding>,
+) {
+    rebinding.0 = None;
+    let text_style = |font_size| TextStyle {
+        font: font_assets.fira_sans.clone(),
+        font_size,
+        color: Color::rgb(0.9, 0.9, 0.9),
+    };
+    let button_bundle = |width| ButtonBundle {
+        style: Style {
+            size: Size::new(Val::Px(width), Val::Px(32.0)),
+            margin: UiRect::all(Val::Px(4.0)),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..Default::default()
+        },
+        background_color: button_colors.normal.into(),
+        ..Default::default()
+    };
+    let row = || NodeBundle {
+        style: Style {
+            flex_direction: FlexDirection::Row,
+            align_items: AlignItems::Center,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            SettingsScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section("Controls", text_style(48.0)));
+            parent.spawn(row()).with_children(|parent| {
+                for (heading, width) in [
+                    ("", 120.0),
+                    ("Key", 168.0),
+                    ("Alternate", 168.0),
+                    ("Gamepad", 168.0),
+                ] {
+                    parent.spawn(
+                        TextBundle::from_section(heading, text_style(20.0)).with_style(Style {
+                            size: Size::new(Val::Px(width), Val::Auto),
+                            ..Default::default()
+                        }),
+                    );
+                }
+            });
+            for control in GameControl::ALL {
+                parent.spawn(row()).with_children(|parent| {
+                    parent.spawn(
+                        TextBundle::from_section(control.name(), text_style(24.0)).with_style(
+                            Style {
+                                size: Size::new(Val::Px(120.0), Val::Auto),
+                                ..Default::default()
+                            },
+                        ),
+                    );
+                    let slots = (0..KEY_SLOTS).map(Slot::Key).chain([Slot::Button]);
+                    for slot in slots {
+                        let binding_slot = BindingSlot { control, slot };
+                        parent
+                            .spawn((button_bundle(160.0), binding_slot))
+                            .with_children(|parent| {
+                                parent.spawn((
+                                    TextBundle::from_section("", text_style(20.0)),
+                                    SlotLabel(binding_slot),
+                                ));
+                            });
+                    }
+                });
+            }
+            parent.spawn(TextBundle::from_section(
+                "In co-op on one keyboard, the second player uses the alternates",
+                text_style(18.0),
+            ));
+            parent.spawn((
+                TextBundle::from_section("Click a binding to change it", text_style(20.0)),
+                SettingsMessage,
+            ));
+            parent.spawn(row()).with_children(|parent| {
+                for button in [SettingsButton::Defaults, SettingsButton::Back] {
+                    parent
+                        .spawn(button_bundle(160.0))
+                        .with_children(|parent| {
+                            parent
+                                .spawn(TextBundle::from_section(button.label(), text_style(28.0)));
+                        })
+                        .insert(button);
+                }
+            });
+        });
+}
+
+fn click_binding_slots(
+    button_colors: Res<ButtonColors>,
+    mut rebinding: ResMut<Rebinding>,
+    mut interaction_query: ButtonInteractions<BindingSlot>,
+    mut message_query: Query<&mut Text, With<SettingsMessage>>,
+) {
+    for (interaction, mut color, binding_slot) in &mut interaction_query {
+        match *interaction {
+            Interaction::Clicked => {
+                rebinding.0 = Some(*binding_slot);
+                let input = match binding_slot.slot {
+                    Slot::Key(_) => "a key",
+                    Slot::Button => "a gamepad button",
+                };
+                message_query.single_mut().sections[0].value = format!(
+                    "Press {} for {}. Escape cancels, Backspace clears.",
+                    input,
+                    binding_slot.control.name()
+                );
+            }
+            Interaction::Hovered => {
+                *color = button_colors.hovered.into();
+            }
+            Interaction::None => {
+                *color = button_colors.normal.into();
+            }
+        }
+    }
+}
+
+fn click_settings_buttons(
+    button_colors: Res<ButtonColors>,
+    mut input_map: ResMut<InputMap>,
+    mut rebinding: ResMut<Rebinding>,
+    mut state: ResMut<NextState<GameState>>,
+    mut interaction_query: ButtonInteractions<SettingsButton>,
+) {
+    for (interaction, mut color, button) in &mut interaction_query {
+        match *interaction {
+            Interaction::Clicked => match button {
+                SettingsButton::Defaults => {
+                    *input_map = InputMap::default();
+                    rebinding.0 = None;
+                }
+                SettingsButton::Back => {
+                    state.set(GameState::Menu);
+                }
+            },
+            Interaction::Hovered => {
+                *color = button_colors.hovered.into();
+            }
+            Interaction::None => {
+                *color = button_colors.normal.into();
+            }
+        }
+    }
+}
+
+fn capture_binding(
+    keyboard_input: Res<Input<KeyCode>>,
+    gamepad_input: Res<Input<GamepadButton>>,
+    mut input_map: ResMut<InputMap>,
+    mut rebinding: ResMut<Rebinding>,
+    mut message_query: Query<&mut Text, With<SettingsMessage>>,
+) {
+    let Some(BindingSlot { control, slot }) = rebinding.0 else {
+        return;
+    };
+
+    let result = if keyboard_input.just_pressed(KeyCode::Escape) {
+        Ok(())
+    } else if keyboard_input.just_pressed(KeyCode::Back) {
+        match slot {
+            Slot::Key(index) => input_map.clear_key(control, index),
+            Slot::Button => input_map.clear_buttons(control),
+        }
+        Ok(())
+    } else {
+        let pressed = match slot {
+            Slot::Key(index) => keyboard_input.get_just_pressed().next().map(|key| {
+                input_map
+                    .bind_key(control, index, *key)
+                    .map_err(|other| (format!("{:?}", key), other))
+            }),
+            Slot::Button => gamepad_input.get_just_pressed().next().map(|button| {
+                input_map
+                    .bind_button(control, button.button_type)
+                    .map_err(|other| (format!("{:?}", button.button_type), other))
+            }),
+        };
+        let Some(result) = pressed else {
+            return;
+        };
+        result
+    };
+
+    rebinding.0 = None;
+    message_query.single_mut().sections[0].value = match result {
+        Ok(()) => "Click a binding to change it".to_string(),
+        Err((input, other)) => format!("{} is already bound to {}", input, other.name()),
+    };
+}
+
+fn update_slot_labels(
+    input_map: Res<InputMap>,
+    rebinding: Res<Rebinding>,
+    mut label_query: Query<(&SlotLabel, &mut Text)>,
+    added_query: Query<(), Added<SlotLabel>>,
+) {
+    if !input_map.is_changed() && !rebinding.is_changed() && added_query.is_empty() {
+        return;
+    }
+    for (SlotLabel(binding_slot), mut text) in &mut label_query {
+        let control = binding_slot.control;
+        text.sections[0].value = if rebinding.0 == Some(*binding_slot) {
+            "...".to_string()
+        } else {
+            let bound = match binding_slot.slot {
+                Slot::Key(index) => input_map
+                    .keys(control)
+                    .get(index)
+                    .map(|key| format!("{:?}", key)),
+                Slot::Button => input_map
+                    .buttons(control)
+                    .first()
+                    .map(|button| format!("{:?}", button)),
+            };
+            bound.unwrap_or_else(|| "-".to_string())
+        };
+    }
+}
+
+fn cleanup_settings(
+    mut commands: Commands,
+    input_map: Res<InputMap>,
+    screen: Query<Entity, With<SettingsScreen>>,
+) {
+    input_map.save();
+    for entity in &screen {
+        commands.entity(entity).despawn_recursive();
+    }
+}
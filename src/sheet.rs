@@ -0,0 +1,71 @@
+use crate::animate::Animation;
+use bevy::asset::{AssetLoader, AssetPath, LoadContext, LoadedAsset};
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use bevy::utils::{BoxedFuture, HashMap};
+use serde::Deserialize;
+
+/// A character's sprite sheet and the animations cut from it, loaded from a `.sheet.ron` file.
+///
+/// The image is a grid of equally sized frames, numbered along each row from the top left. Clips
+/// are looked up by name, so a new skin only needs its own sheet with the same clip names.
+#[derive(Deserialize, TypeUuid)]
+#[uuid = "3e9b7c21-8a4f-4d0e-b6c5-1f2a9d8e7c64"]
+pub struct SpriteSheet {
+    /// Path of the image, relative to the assets folder.
+    pub image: String,
+    /// Size of each frame in pixels.
+    pub tile_size: Vec2,
+    pub columns: usize,
+    pub rows: usize,
+    /// Pixels a second the walk clips are timed for. They play faster or slower to match.
+    pub walk_speed: f32,
+    clips: HashMap<String, Animation>,
+    #[serde(skip)]
+    pub atlas: Handle<TextureAtlas>,
+}
+
+impl SpriteSheet {
+    pub fn clip(&self, name: &str) -> Option<&Animation> {
+        self.clips.get(name)
+    }
+}
+
+#[derive(Default)]
+pub struct SpriteSheetLoader {
+    /// Leave out the image and atlas, for running without a renderer. Only the clips are loaded.
+    pub clips_only: bool,
+}
+
+impl AssetLoader for SpriteSheetLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let mut sheet = ron::de::from_bytes::<SpriteSheet>(bytes)?;
+            if self.clips_only {
+                load_context.set_default_asset(LoadedAsset::new(sheet));
+                return Ok(());
+            }
+
+            let path = AssetPath::from(sheet.image.as_str()).to_owned();
+            let atlas = TextureAtlas::from_grid(
+                load_context.get_handle(path.clone()),
+                sheet.tile_size,
+                sheet.columns,
+                sheet.rows,
+                None,
+                None,
+            );
+            sheet.atlas = load_context.set_labeled_asset("atlas", LoadedAsset::new(atlas));
+            load_context.set_default_asset(LoadedAsset::new(sheet).with_dependency(path));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["sheet.ron"]
+    }
+}
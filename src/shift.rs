@@ -0,0 +1,53 @@
+use crate::level::Level;
+use crate::loading::LevelAssets;
+use crate::{starting_session, GameState};
+use bevy::prelude::*;
+
+pub struct ShiftPlugin;
+
+/// This plugin times the shift, and ends the session with the results once it is over.
+impl Plugin for ShiftPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(
+            start_shift
+                .run_if(starting_session)
+                .in_schedule(OnEnter(GameState::Playing)),
+        )
+        .add_system(tick_shift.in_set(OnUpdate(GameState::Playing)));
+    }
+}
+
+#[derive(Resource)]
+pub struct Shift {
+    timer: Timer,
+}
+
+impl Shift {
+    /// Seconds since the shift started.
+    pub fn elapsed(&self) -> f32 {
+        self.timer.elapsed_secs()
+    }
+
+    /// Seconds until the shift is over.
+    pub fn remaining(&self) -> f32 {
+        self.timer.remaining_secs()
+    }
+}
+
+fn start_shift(mut commands: Commands, level_assets: Res<LevelAssets>, levels: Res<Assets<Level>>) {
+    let level = levels
+        .get(&level_assets.bar)
+        .expect("Level is loaded before entering Playing");
+
+    commands.insert_resource(Shift {
+        timer: Timer::from_seconds(level.shift.length, TimerMode::Once),
+    });
+}
+
+fn tick_shift(time: Res<Time>, mut shift: ResMut<Shift>, mut state: ResMut<NextState<GameState>>) {
+    shift.timer.tick(time.delta());
+    if shift.timer.just_finished() {
+        println!("Shift over");
+        state.set(GameState::Results);
+    }
+}
@@ -0,0 +1,312 @@
+use crate::loading::{HeadlessLoadingPlugin, TextureAssets};
+use crate::npc::NPC;
+use crate::options::LaunchOptions;
+use crate::player::{Interactable, Player};
+use crate::rng::SeedChoice;
+use crate::score::{Score, ScoreEvent};
+use crate::shift::Shift;
+use crate::tilemap::TileMap;
+use crate::world::Tile;
+use crate::{add_headless_gameplay, GameState, Session};
+use bevy::app::AppExit;
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+use bevy::utils::HashMap;
+use std::time::Duration;
+
+pub struct SimulationPlugin;
+
+/// This plugin plays shift after shift of the bar without a window, renderer or audio, on a fixed
+/// time step and as fast as it can, then prints how they went. Add it to `MinimalPlugins`, with
+/// the asset, hierarchy and transform plugins.
+///
+/// Nobody is at the controls, so a stand-in bartender serves each order once the customer has
+/// waited at the bar for `SimulationSettings::serve_after`.
+impl Plugin for SimulationPlugin {
+    fn build(&self, app: &mut App) {
+        let settings = app
+            .world
+            .get_resource::<SimulationSettings>()
+            .cloned()
+            .unwrap_or_default();
+        app.insert_resource(LaunchOptions {
+            seed: settings.seed,
+            ..default()
+        })
+        .insert_resource(settings);
+
+        add_headless_gameplay(app)
+            .init_resource::<SimulationStats>()
+            .add_plugin(HeadlessLoadingPlugin)
+            .add_system(step_time.in_base_set(CoreSet::Last))
+            .add_systems((serve_orders, track_waiting).in_set(OnUpdate(GameState::Playing)))
+            .add_system(next_shift.in_schedule(OnEnter(GameState::Results)));
+    }
+}
+
+#[derive(Resource, Clone, Debug)]
+pub struct SimulationSettings {
+    /// Simulated minutes to run for, over as many shifts as that takes.
+    pub minutes: f32,
+    /// Length of each frame. Longer runs faster, but customers may overshoot the tiles they
+    /// walk to.
+    pub step: Duration,
+    /// Seconds an order waits at the bar before it is served, or `None` to serve nobody.
+    pub serve_after: Option<f32>,
+    /// Seed of the first shift, the next shift taking the one after. Random if `None`.
+    pub seed: Option<u64>,
+}
+
+impl Default for SimulationSettings {
+    fn default() -> Self {
+        SimulationSettings {
+            minutes: 600.,
+            step: Duration::from_secs_f32(1. / 60.),
+            serve_after: Some(10.),
+            seed: None,
+        }
+    }
+}
+
+impl SimulationSettings {
+    /// Reads `--minutes`, `--step`, `--serve-after` (seconds, or `never`) and `--seed` from the
+    /// process arguments. Anything else is skipped with a warning.
+    pub fn from_args() -> Self {
+        let mut settings = SimulationSettings::default();
+        settings.read_args(std::env::args().skip(1));
+        settings
+    }
+
+    fn read_args(&mut self, mut args: impl Iterator<Item = String>) {
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--minutes" => match args.next().map(|value| value.parse()) {
+                    Some(Ok(minutes)) => self.minutes = minutes,
+                    _ => println!("--minutes needs a number"),
+                },
+                "--step" => match args.next().map(|value| value.parse::<f32>()) {
+                    Some(Ok(step)) if step.is_finite() && step > 0. => {
+                        self.step = Duration::from_secs_f32(step)
+                    }
+                    _ => println!("--step needs a number of seconds above zero"),
+                },
+                "--serve-after" => match args.next().as_deref() {
+                    Some("never") => self.serve_after = None,
+                    Some(value) => match value.parse() {
+                        Ok(seconds) => self.serve_after = Some(seconds),
+                        Err(_) => println!("--serve-after needs a number of seconds, or never"),
+                    },
+                    None => println!("--serve-after needs a number of seconds, or never"),
+                },
+                "--seed" => match args.next().map(|value| value.parse()) {
+                    Some(Ok(seed)) => self.seed = Some(seed),
+                    _ => println!("--seed needs a number"),
+                },
+                _ => println!("Ignoring unknown argument {}", arg),
+            }
+        }
+    }
+}
+
+/// Where the stand-in bartender finds the container a customer is waiting at.
+#[derive(SystemParam)]
+struct Bar<'w, 's> {
+    tile_map_query: Query<'w, 's, (&'static TileMap, &'static Transform)>,
+    tile_query: Query<'w, 's, &'static Children, With<Tile>>,
+    interactable_query: Query<'w, 's, &'static mut Interactable>,
+}
+
+/// How the shift that just ended went.
+#[derive(SystemParam)]
+struct ShiftResults<'w> {
+    score: Res<'w, Score>,
+    shift: Res<'w, Shift>,
+}
+
+/// Totals over every shift simulated so far.
+#[derive(Resource, Default)]
+struct SimulationStats {
+    shifts: u64,
+    seconds: f32,
+    score: i32,
+    events: HashMap<ScoreEvent, usize>,
+    /// Customer-seconds spent between ordering and being served, or walking out.
+    waiting: f32,
+}
+
+impl SimulationStats {
+    fn add_shift(&mut self, score: &Score, seconds: f32) {
+        self.shifts += 1;
+        self.seconds += seconds;
+        self.score += score.total();
+        for event in ScoreEvent::ALL {
+            *self.events.entry(event).or_default() += score.count(event);
+        }
+    }
+
+    fn count(&self, event: ScoreEvent) -> usize {
+        self.events.get(&event).copied().unwrap_or(0)
+    }
+
+    fn report(&self) {
+        let minutes = self.seconds / 60.;
+        println!();
+        println!(
+            "Simulated {:.0} minutes over {} shifts",
+            minutes, self.shifts
+        );
+        println!("Score per minute: {:.1}", self.score as f32 / minutes);
+        println!("Per minute:");
+        for event in ScoreEvent::ALL {
+            println!(
+                "  {:<12}{:>8.2}  ({:+} points each)",
+                format!("{:?}", event),
+                self.count(event) as f32 / minutes,
+                event.points()
+            );
+        }
+        let orders = self.count(ScoreEvent::RightDrink)
+            + self.count(ScoreEvent::WrongDrink)
+            + self.count(ScoreEvent::RawFruit)
+            + self.count(ScoreEvent::WalkOut);
+        println!(
+            "Customers waiting on average: {:.2}",
+            self.waiting / self.seconds
+        );
+        if orders > 0 {
+            println!("Wait per order: {:.1}s", self.waiting / orders as f32);
+        }
+    }
+}
+
+/// Every frame is `SimulationSettings::step` long, however long it really took.
+fn step_time(
+    time: Res<Time>,
+    settings: Res<SimulationSettings>,
+    mut strategy: ResMut<TimeUpdateStrategy>,
+) {
+    let last_update = time.last_update().unwrap_or_else(|| time.startup());
+    *strategy = TimeUpdateStrategy::ManualInstant(last_update + settings.step);
+}
+
+/// The stand-in bartender. Puts the order in the container a customer is waiting at.
+fn serve_orders(
+    mut commands: Commands,
+    time: Res<Time>,
+    settings: Res<SimulationSettings>,
+    textures: Res<TextureAssets>,
+    mut waited: Local<HashMap<Entity, f32>>,
+    npc_query: Query<(Entity, &NPC, &Player, &Transform)>,
+    mut bar: Bar,
+) {
+    let Some(serve_after) = settings.serve_after else {
+        return;
+    };
+    let (tile_map, tile_map_transform) = bar.tile_map_query.single();
+
+    waited.retain(|entity, _| {
+        npc_query
+            .get(*entity)
+            .is_ok_and(|(_, npc, _, _)| npc.waiting_at_bar().is_some())
+    });
+    for (entity, npc, player, transform) in &npc_query {
+        let Some(order) = npc.waiting_at_bar() else {
+            continue;
+        };
+        let elapsed = waited.entry(entity).or_default();
+        *elapsed += time.delta_seconds();
+        if *elapsed < serve_after {
+            continue;
+        }
+
+        // Customers wait facing the container.
+        let tile = tile_map.world_to_tile(tile_map_transform.translation, transform.translation)
+            + player.heading.as_offset();
+        let Some(children) = tile_map
+            .tile_at(tile)
+            .and_then(|tile| bar.tile_query.get(tile).ok())
+        else {
+            continue;
+        };
+        for child in children.iter() {
+            let Ok(mut interactable) = bar.interactable_query.get_mut(*child) else {
+                continue;
+            };
+            let Interactable::Container(_) = *interactable else {
+                continue;
+            };
+            let item_entity = order.clone().spawn(Vec3::ZERO, &mut commands, &textures);
+            if interactable.consume(*child, order.clone(), item_entity, &mut commands) {
+                *elapsed = 0.;
+            } else {
+                // Someone else's order is still in it.
+                commands.entity(item_entity).despawn();
+            }
+            break;
+        }
+    }
+}
+
+fn track_waiting(time: Res<Time>, mut stats: ResMut<SimulationStats>, npc_query: Query<&NPC>) {
+    let waiting = npc_query.iter().filter(|npc| npc.is_waiting()).count();
+    stats.waiting += waiting as f32 * time.delta_seconds();
+}
+
+/// Starts the next shift straight away, until enough minutes have been simulated.
+fn next_shift(
+    mut commands: Commands,
+    results: ShiftResults,
+    settings: Res<SimulationSettings>,
+    mut stats: ResMut<SimulationStats>,
+    mut seed_choice: ResMut<SeedChoice>,
+    mut state: ResMut<NextState<GameState>>,
+    mut exit: EventWriter<AppExit>,
+) {
+    stats.add_shift(&results.score, results.shift.elapsed());
+    if stats.seconds >= settings.minutes * 60. {
+        stats.report();
+        exit.send(AppExit);
+        return;
+    }
+    if let Some(seed) = settings.seed {
+        *seed_choice = SeedChoice::Fixed(seed.wrapping_add(stats.shifts));
+    }
+    commands.remove_resource::<Session>();
+    state.set(GameState::Playing);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_args(args: &[&str]) -> SimulationSettings {
+        let mut settings = SimulationSettings::default();
+        settings.read_args(args.iter().map(|arg| arg.to_string()));
+        settings
+    }
+
+    #[test]
+    fn unknown_arguments_keep_the_next_one() {
+        let settings = read_args(&["--bogus", "--minutes", "5", "--serve-after", "never"]);
+        assert_eq!(settings.minutes, 5.);
+        assert_eq!(settings.serve_after, None);
+    }
+
+    #[test]
+    fn steps_must_be_positive() {
+        let default_step = SimulationSettings::default().step;
+        for step in ["-1", "0", "inf", "NaN", "soon"] {
+            assert_eq!(
+                read_args(&["--step", step]).step,
+                default_step,
+                "--step {}",
+                step
+            );
+        }
+        assert_eq!(
+            read_args(&["--step", "0.5"]).step,
+            Duration::from_secs_f32(0.5)
+        );
+    }
+}
@@ -0,0 +1,66 @@
+use directories::ProjectDirs;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Where on the user's machine a file is kept.
+#[derive(Clone, Copy)]
+pub enum Location {
+    /// Things the game records, like high scores.
+    Data,
+    /// Things the player chooses, like key bindings.
+    Config,
+}
+
+/// `None` where there is no such directory, e.g. in a browser.
+fn file_path(location: Location, file_name: &str) -> Option<PathBuf> {
+    let dirs = ProjectDirs::from("", "", "lunas-libations")?;
+    let dir = match location {
+        Location::Data => dirs.data_dir(),
+        Location::Config => dirs.config_dir(),
+    };
+    Some(dir.join(file_name))
+}
+
+/// Reads a RON file. A missing file is `None`, and so is a corrupt one, after saying so.
+pub fn load<T: DeserializeOwned>(location: Location, file_name: &str) -> Option<T> {
+    load_from(&file_path(location, file_name)?)
+}
+
+/// Reads a RON file from anywhere, like `load`.
+pub fn load_from<T: DeserializeOwned>(path: &Path) -> Option<T> {
+    let contents = fs::read_to_string(path).ok()?;
+    match ron::from_str(&contents) {
+        Ok(value) => Some(value),
+        Err(error) => {
+            println!("Ignoring corrupt {}: {}", path.display(), error);
+            None
+        }
+    }
+}
+
+/// Writes a RON file. Failing to is reported, but not fatal.
+pub fn save<T: Serialize>(location: Location, file_name: &str, value: &T) {
+    let Some(path) = file_path(location, file_name) else {
+        return;
+    };
+    save_to(&path, value);
+}
+
+/// Writes a RON file anywhere, like `save`.
+pub fn save_to<T: Serialize>(path: &Path, value: &T) {
+    if let Err(error) = write(path, value) {
+        println!("Failed to save {}: {}", path.display(), error);
+    }
+}
+
+fn write<T: Serialize>(path: &Path, value: &T) -> Result<(), Box<dyn Error>> {
+    let contents = ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default())?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, contents)?;
+    Ok(())
+}
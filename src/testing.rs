@@ -0,0 +1,258 @@
+//! A harness for testing gameplay: the game without a window, on a small level, with the
+//! bartenders' actions scripted frame by frame.
+
+use crate::actions::Actions;
+use crate::level::Level;
+use crate::loading::{FontAssets, LevelAssets, RecipeAssets, SheetAssets, TextureAssets};
+use crate::npc::NPC;
+use crate::options::LaunchOptions;
+use crate::player::{spawn_npc, Interactable, UserControllable};
+use crate::recipe::RecipeBook;
+use crate::rng::GameRng;
+use crate::sheet::SpriteSheet;
+use crate::tilemap::TileMap;
+use crate::{add_headless_gameplay, GameState};
+use bevy::asset::AssetPlugin;
+use bevy::ecs::system::SystemState;
+use bevy::hierarchy::HierarchyPlugin;
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+use bevy::transform::TransformPlugin;
+use std::time::Duration;
+
+/// Every frame is this long.
+pub const FRAME: Duration = Duration::from_nanos(1_000_000_000 / 60);
+
+/// Give up on anything that takes longer than this, in frames.
+const TIMEOUT: usize = 600;
+
+/// What `spawn_npc` needs from the world, and where to put them.
+type NpcSpawning = (
+    Commands<'static, 'static>,
+    Res<'static, SheetAssets>,
+    Res<'static, Assets<SpriteSheet>>,
+    ResMut<'static, GameRng>,
+    Query<'static, 'static, (&'static TileMap, &'static Transform)>,
+);
+
+/// The same characters as the bar. Customers only turn up on their own, and leave, on levels
+/// with an entrance.
+const LEGEND: &str = r#"{
+    '.': (tile: Floor),
+    'E': (tile: Entrance),
+    'P': (tile: Floor, spawn: Some(Player)),
+    'T': (tile: Table),
+    'B': (tile: BarBack),
+    'o': (tile: BarBack, interactable: Some(Spawner(Orange))),
+    'b': (tile: BarBack, interactable: Some(Spawner(Banana))),
+    'c': (tile: BarBack, interactable: Some(Spawner(Cherry))),
+    't': (tile: BarBack, interactable: Some(Trash)),
+    '=': (tile: Bar),
+    'm': (tile: Bar, interactable: Some(Mixer)),
+    'u': (tile: Bar, interactable: Some(Container)),
+}"#;
+
+/// One frame of what a bartender does.
+#[derive(Clone, Copy)]
+pub enum Act {
+    Idle,
+    Walk(Vec2),
+    PickUp,
+    Interact,
+}
+
+pub struct TestApp {
+    pub app: App,
+}
+
+impl TestApp {
+    /// Starts a session on a level of `rows`, listed top row first as in a level file. The
+    /// recipes are the game's own, and the random numbers are the same every run.
+    pub fn new(rows: &[&str]) -> Self {
+        let mut app = App::new();
+        app.insert_resource(LaunchOptions {
+            seed: Some(0),
+            ..default()
+        })
+        .add_plugins(MinimalPlugins)
+        .add_plugin(AssetPlugin::default())
+        .add_plugin(HierarchyPlugin)
+        .add_plugin(TransformPlugin)
+        .add_asset::<Level>()
+        .add_asset::<RecipeBook>()
+        .add_asset::<SpriteSheet>();
+        add_headless_gameplay(&mut app);
+
+        let bar = app.world.resource_mut::<Assets<Level>>().add(level(rows));
+        let recipes =
+            ron::from_str::<RecipeBook>(include_str!("../assets/recipes/drinks.recipes.ron"))
+                .expect("The recipes parse");
+        let book = app.world.resource_mut::<Assets<RecipeBook>>().add(recipes);
+        let mut sheet = |source: &str| {
+            let sheet = ron::from_str::<SpriteSheet>(source).expect("The sheet parses");
+            app.world.resource_mut::<Assets<SpriteSheet>>().add(sheet)
+        };
+        let sheets = SheetAssets {
+            luna: sheet(include_str!("../assets/sheets/luna.sheet.ron")),
+            bartender2: sheet(include_str!("../assets/sheets/bartender2.sheet.ron")),
+            npc1: sheet(include_str!("../assets/sheets/npc1.sheet.ron")),
+        };
+        app.insert_resource(LevelAssets { bar })
+            .insert_resource(RecipeAssets { book })
+            .insert_resource(sheets)
+            .init_resource::<FontAssets>()
+            .init_resource::<TextureAssets>();
+        app.world
+            .resource_mut::<NextState<GameState>>()
+            .set(GameState::Playing);
+
+        let mut test_app = TestApp { app };
+        test_app.run(1);
+        test_app
+    }
+
+    pub fn world(&mut self) -> &mut World {
+        &mut self.app.world
+    }
+
+    pub fn run(&mut self, frames: usize) {
+        for _ in 0..frames {
+            let time = self.app.world.resource::<Time>();
+            let last_update = time.last_update().unwrap_or_else(|| time.startup());
+            self.app
+                .insert_resource(TimeUpdateStrategy::ManualInstant(last_update + FRAME));
+            self.app.update();
+        }
+    }
+
+    /// Runs until `done`, failing the test if that takes too long.
+    pub fn run_until(&mut self, what: &str, mut done: impl FnMut(&mut World) -> bool) {
+        for _ in 0..TIMEOUT {
+            if done(&mut self.app.world) {
+                return;
+            }
+            self.run(1);
+        }
+        panic!("Timed out waiting until {}", what);
+    }
+
+    /// The bartender of player `index`, spawned on the level's `index`th 'P'.
+    pub fn bartender(&mut self, index: usize) -> Entity {
+        let mut query = self.app.world.query::<(Entity, &UserControllable)>();
+        query
+            .iter(&self.app.world)
+            .find(|(_, controllable)| controllable.player == index)
+            .map(|(entity, _)| entity)
+            .expect("No such bartender")
+    }
+
+    pub fn spawn_npc(&mut self, tile: IVec2) -> Entity {
+        let mut state: SystemState<NpcSpawning> = SystemState::new(&mut self.app.world);
+        let (mut commands, sheet_assets, sheets, mut rng, tile_map_query) =
+            state.get_mut(&mut self.app.world);
+        let (tile_map, tile_map_transform) = tile_map_query.single();
+        let position = tile_map.tile_to_world(tile_map_transform.translation, tile);
+        let entity = spawn_npc(&mut commands, &sheet_assets, &sheets, position, &mut *rng);
+        state.apply(&mut self.app.world);
+        entity
+    }
+
+    /// Spawns a customer on `tile`, and runs until they are at the bar waiting for their order.
+    pub fn waiting_customer(&mut self, tile: IVec2) -> Entity {
+        let npc = self.spawn_npc(tile);
+        self.run_until("the customer waits at the bar", |world| {
+            world.get::<NPC>(npc).unwrap().waiting_at_bar().is_some()
+        });
+        npc
+    }
+
+    /// The tile an entity is standing on.
+    pub fn tile_of(&mut self, entity: Entity) -> IVec2 {
+        let translation = self.app.world.get::<Transform>(entity).unwrap().translation;
+        let mut query = self.app.world.query::<(&TileMap, &Transform)>();
+        let (tile_map, transform) = query.single(&self.app.world);
+        tile_map.world_to_tile(transform.translation, translation)
+    }
+
+    /// Moves an entity onto `tile`, wherever it was.
+    pub fn place(&mut self, entity: Entity, tile: IVec2) {
+        let mut query = self.app.world.query::<(&TileMap, &Transform)>();
+        let (tile_map, transform) = query.single(&self.app.world);
+        let position = tile_map.tile_to_world(transform.translation, tile);
+        let mut transform = self.app.world.get_mut::<Transform>(entity).unwrap();
+        transform.translation = position.truncate().extend(transform.translation.z);
+    }
+
+    /// The interactable placed on a tile.
+    pub fn interactable_at(&mut self, tile: IVec2) -> &Interactable {
+        let mut query = self.app.world.query::<&TileMap>();
+        let tile_entity = query
+            .single(&self.app.world)
+            .tile_at(tile)
+            .expect("No tile there");
+        let children = self.app.world.get::<Children>(tile_entity).unwrap();
+        children
+            .iter()
+            .find_map(|child| self.app.world.get::<Interactable>(*child))
+            .expect("Nothing on that tile")
+    }
+
+    /// Does `act` for one frame.
+    pub fn act(&mut self, bartender: Entity, act: Act) {
+        let mut actions = self.app.world.get_mut::<Actions>(bartender).unwrap();
+        actions.movement = match act {
+            Act::Walk(direction) => Some(direction),
+            _ => None,
+        };
+        actions.pick_up.update(matches!(act, Act::PickUp));
+        actions.interact.update(matches!(act, Act::Interact));
+        self.run(1);
+    }
+
+    /// Presses and releases grab.
+    pub fn pick_up(&mut self, bartender: Entity) {
+        self.act(bartender, Act::PickUp);
+        self.act(bartender, Act::Idle);
+    }
+
+    /// Presses and releases interact.
+    pub fn interact(&mut self, bartender: Entity) {
+        self.act(bartender, Act::Interact);
+        self.act(bartender, Act::Idle);
+    }
+
+    /// Turns to face `direction`, with a step that way.
+    pub fn face(&mut self, bartender: Entity, direction: Vec2) {
+        self.act(bartender, Act::Walk(direction));
+        self.act(bartender, Act::Idle);
+    }
+
+    /// Walks in straight lines until on `tile`.
+    pub fn walk_to(&mut self, bartender: Entity, tile: IVec2) {
+        for _ in 0..TIMEOUT {
+            let offset = tile - self.tile_of(bartender);
+            if offset == IVec2::ZERO {
+                self.act(bartender, Act::Idle);
+                return;
+            }
+            let direction = if offset.x != 0 {
+                Vec2::new(offset.x.signum() as f32, 0.)
+            } else {
+                Vec2::new(0., offset.y.signum() as f32)
+            };
+            self.act(bartender, Act::Walk(direction));
+        }
+        panic!("Couldn't walk to {:?}", tile);
+    }
+}
+
+fn level(rows: &[&str]) -> Level {
+    let rows: Vec<String> = rows.iter().map(|row| format!("{:?}", row)).collect();
+    ron::from_str(&format!(
+        "(name: \"Test\", shift: (length: 3600.0, capacity: 10, arrivals: []), \
+         rows: [{}], legend: {})",
+        rows.join(", "),
+        LEGEND
+    ))
+    .expect("The test level parses")
+}
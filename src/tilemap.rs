@@ -1,4 +1,13 @@
 use bevy::prelude::*;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+const NEIGHBORS: [IVec2; 4] = [
+    IVec2::new(0, 1),
+    IVec2::new(0, -1),
+    IVec2::new(-1, 0),
+    IVec2::new(1, 0),
+];
 
 #[derive(Component)]
 pub struct TileMap {
@@ -46,47 +55,60 @@ impl TileMap {
     }
 
     pub fn transform_tiles(&self, query: &mut Query<&mut Transform, Without<TileMap>>) {
-        for x in 0..self.size.x {
-            for y in 0..self.size.y {
-                let tile_index = (x + (y * self.size.x)) as usize;
-                if let Some(tile) = self.tiles[tile_index] {
-                    if let Ok(mut tile_transform) = query.get_mut(tile) {
-                        let position = Vec3::new(
-                            (x * self.tile_size.x) as f32,
-                            (y * self.tile_size.y) as f32,
-                            self.tile_z(&IVec2::new(x, y)),
-                        );
-                        tile_transform.translation = position;
-                    }
-                }
+        for (tile, entity) in self.iter() {
+            if let Ok(mut tile_transform) = query.get_mut(entity) {
+                // Tiles are children of the map, so placed before its scale.
+                let corner = self.tile_corner(tile) / self.scale.as_vec2().extend(1.);
+                tile_transform.translation = corner.truncate().extend(tile_transform.translation.z);
             }
         }
     }
 
-    /// Z-level of passed tile.
-    pub fn tile_z(&self, tile: &IVec2) -> f32 {
-        (self.size.y - tile.y) as f32
+    /// Size of a tile in tile space, which is also its size in the world.
+    pub fn tile_extent(&self) -> Vec2 {
+        (self.tile_size * self.scale).as_vec2()
     }
 
+    /// Convert a world point to tile space, which is relative to the map at `origin`. Z is kept.
     pub fn to_tile_space(&self, origin: Vec3, point: Vec3) -> Vec3 {
         let mut result = point - origin;
         result.z = point.z;
         result
     }
 
-    // Convert tile space point to tile x,y
+    /// Convert a tile space point back to the world. Z is kept.
+    pub fn tile_space_to_world(&self, origin: Vec3, point: Vec3) -> Vec3 {
+        let mut result = point + origin;
+        result.z = point.z;
+        result
+    }
+
+    /// Convert tile space point to the index of the tile it is on. Tile 0,0 is bottom left, and
+    /// points left of or below it are on negative tiles.
     pub fn to_tile(&self, point: Vec3) -> IVec2 {
-        // tile space is 0,0 bottom left
-        // tile index is 0,0 bottom left
-        // index y = space 0, index 0 = space y
-        let scaled = IVec2::new(point.x as i32 / self.scale.x, point.y as i32 / self.scale.y);
-        IVec2::new(scaled.x / self.tile_size.x, scaled.y / self.tile_size.y)
+        (point.truncate() / self.tile_extent()).floor().as_ivec2()
     }
 
-    pub fn camera_to_tile(&self, origin: Vec3, point: Vec3) -> IVec2 {
+    /// Bottom left corner of a tile, in tile space.
+    pub fn tile_corner(&self, tile: IVec2) -> Vec3 {
+        (tile.as_vec2() * self.tile_extent()).extend(0.)
+    }
+
+    /// Middle of a tile, in tile space.
+    pub fn tile_center(&self, tile: IVec2) -> Vec3 {
+        self.tile_corner(tile) + (self.tile_extent() / 2.).extend(0.)
+    }
+
+    /// The tile a world point is on, for the map at `origin`.
+    pub fn world_to_tile(&self, origin: Vec3, point: Vec3) -> IVec2 {
         self.to_tile(self.to_tile_space(origin, point))
     }
 
+    /// Where to stand on a tile in the world, for the map at `origin`: in the middle.
+    pub fn tile_to_world(&self, origin: Vec3, tile: IVec2) -> Vec3 {
+        self.tile_space_to_world(origin, self.tile_center(tile))
+    }
+
     pub fn tile_at(&self, point: IVec2) -> Option<Entity> {
         if point.x < 0 || point.x >= self.size.x {
             None
@@ -111,6 +133,107 @@ impl TileMap {
         None
     }
 
+    /// Find a route from `start` to `goal` using A*, moving only between adjacent tiles.
+    ///
+    /// `passable` is called with the entity of each tile the route may cross. Returns the tiles to
+    /// visit in order, excluding `start` and ending with `goal`, or `None` if `goal` can't be
+    /// reached.
+    pub fn find_path(
+        &self,
+        start: IVec2,
+        goal: IVec2,
+        passable: impl Fn(Entity) -> bool,
+    ) -> Option<Vec<IVec2>> {
+        self.tile_at(start)?;
+        if start == goal {
+            return Some(Vec::new());
+        }
+        if !self.tile_at(goal).is_some_and(&passable) {
+            return None;
+        }
+
+        let heuristic = |point: IVec2| (goal - point).abs();
+        let mut cost = vec![i32::MAX; self.tiles.len()];
+        let mut came_from: Vec<Option<IVec2>> = vec![None; self.tiles.len()];
+        // Ordered by estimated total cost, then insertion order to keep routes stable.
+        let mut open = BinaryHeap::new();
+        let mut order = 0;
+
+        cost[self.tile_index(start)] = 0;
+        let h = heuristic(start);
+        open.push(Reverse((h.x + h.y, order, start.x, start.y)));
+
+        while let Some(Reverse((_, _, x, y))) = open.pop() {
+            let current = IVec2::new(x, y);
+            if current == goal {
+                let mut path = vec![goal];
+                let mut point = goal;
+                while let Some(previous) = came_from[self.tile_index(point)] {
+                    if previous == start {
+                        break;
+                    }
+                    path.push(previous);
+                    point = previous;
+                }
+                path.reverse();
+                return Some(path);
+            }
+
+            let next_cost = cost[self.tile_index(current)] + 1;
+            for offset in NEIGHBORS {
+                let neighbor = current + offset;
+                let Some(entity) = self.tile_at(neighbor) else {
+                    continue;
+                };
+                let index = self.tile_index(neighbor);
+                if next_cost >= cost[index] || !passable(entity) {
+                    continue;
+                }
+                cost[index] = next_cost;
+                came_from[index] = Some(current);
+                order += 1;
+                let h = heuristic(neighbor);
+                open.push(Reverse((
+                    next_cost + h.x + h.y,
+                    order,
+                    neighbor.x,
+                    neighbor.y,
+                )));
+            }
+        }
+
+        None
+    }
+
+    /// Every tile that can be walked to from `start`, including `start` itself. `passable` is
+    /// called with the entity of each tile, as for `find_path`.
+    pub fn reachable(&self, start: IVec2, passable: impl Fn(Entity) -> bool) -> Vec<IVec2> {
+        if self.tile_at(start).is_none() {
+            return Vec::new();
+        }
+
+        let mut seen = vec![false; self.tiles.len()];
+        seen[self.tile_index(start)] = true;
+        let mut reached = vec![start];
+        let mut next = 0;
+        while let Some(&current) = reached.get(next) {
+            next += 1;
+            for offset in NEIGHBORS {
+                let neighbor = current + offset;
+                let Some(entity) = self.tile_at(neighbor) else {
+                    continue;
+                };
+                let index = self.tile_index(neighbor);
+                if seen[index] || !passable(entity) {
+                    continue;
+                }
+                seen[index] = true;
+                reached.push(neighbor);
+            }
+        }
+        reached
+    }
+
     fn tile_index(&self, point: IVec2) -> usize {
         (point.x + (point.y * self.size.x)) as usize
     }
@@ -161,3 +284,200 @@ impl<'a> Iterator for TileMapIterator<'a> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::utils::HashSet;
+    use proptest::prelude::*;
+
+    /// The bar: 25x18 tiles of 16 pixels, drawn at double size, from -400,-300.
+    fn bar() -> (TileMap, Vec3) {
+        let tile_map = TileMap::new(IVec2::new(25, 18), IVec2::splat(16), IVec2::splat(2));
+        (tile_map, Vec3::new(-400., -300., 0.))
+    }
+
+    #[test]
+    fn points_left_of_and_below_the_map_are_on_negative_tiles() {
+        let (tile_map, _) = bar();
+        assert_eq!(
+            tile_map.to_tile(Vec3::new(-0.5, -0.5, 0.)),
+            IVec2::new(-1, -1)
+        );
+        assert_eq!(
+            tile_map.to_tile(Vec3::new(-32., 31.9, 0.)),
+            IVec2::new(-1, 0)
+        );
+        assert_eq!(
+            tile_map.to_tile(Vec3::new(-32.1, 0., 0.)),
+            IVec2::new(-2, 0)
+        );
+    }
+
+    #[test]
+    fn tile_edges_belong_to_the_tile_above_and_right() {
+        let (tile_map, _) = bar();
+        assert_eq!(tile_map.to_tile(Vec3::ZERO), IVec2::ZERO);
+        assert_eq!(tile_map.to_tile(Vec3::new(32., 64., 0.)), IVec2::new(1, 2));
+        assert_eq!(
+            tile_map.to_tile(Vec3::new(31.9, 63.9, 0.)),
+            IVec2::new(0, 1)
+        );
+    }
+
+    #[test]
+    fn tiles_are_placed_in_the_world() {
+        let (tile_map, origin) = bar();
+        assert_eq!(
+            tile_map.tile_corner(IVec2::new(2, 1)),
+            Vec3::new(64., 32., 0.)
+        );
+        assert_eq!(
+            tile_map.tile_center(IVec2::new(2, 1)),
+            Vec3::new(80., 48., 0.)
+        );
+        assert_eq!(
+            tile_map.tile_to_world(origin, IVec2::new(2, 1)),
+            Vec3::new(-320., -252., 0.)
+        );
+        assert_eq!(
+            tile_map.world_to_tile(origin, Vec3::new(-400., -300., 3.)),
+            IVec2::ZERO
+        );
+        assert_eq!(
+            tile_map.world_to_tile(origin, Vec3::new(-401., -300., 3.)),
+            IVec2::new(-1, 0)
+        );
+    }
+
+    /// A map from rows of `.` for floor and `#` for wall, top row first, and which are walls.
+    fn maze(rows: &[&str]) -> (TileMap, HashSet<Entity>) {
+        let size = IVec2::new(rows[0].len() as i32, rows.len() as i32);
+        let mut tile_map = TileMap::new(size, IVec2::splat(16), IVec2::splat(2));
+        let mut walls = HashSet::new();
+        for (row, line) in rows.iter().enumerate() {
+            for (x, c) in line.chars().enumerate() {
+                let tile = IVec2::new(x as i32, size.y - 1 - row as i32);
+                let index = tile_map.tile_index(tile);
+                let entity = Entity::from_raw(index as u32);
+                tile_map.tiles[index] = Some(entity);
+                if c == '#' {
+                    walls.insert(entity);
+                }
+            }
+        }
+        (tile_map, walls)
+    }
+
+    fn find_path(rows: &[&str], start: IVec2, goal: IVec2) -> Option<Vec<IVec2>> {
+        let (tile_map, walls) = maze(rows);
+        tile_map.find_path(start, goal, |entity| !walls.contains(&entity))
+    }
+
+    #[test]
+    fn routes_exclude_the_start_and_end_at_the_goal() {
+        let path = find_path(&["...."], IVec2::new(0, 0), IVec2::new(3, 0));
+        assert_eq!(
+            path,
+            Some(vec![IVec2::new(1, 0), IVec2::new(2, 0), IVec2::new(3, 0)])
+        );
+    }
+
+    #[test]
+    fn routes_go_around_blocking_tiles() {
+        let rows = ["...", ".#.", "..."];
+        let (start, goal) = (IVec2::new(0, 1), IVec2::new(2, 1));
+        let path = find_path(&rows, start, goal).unwrap();
+
+        assert_eq!(path.len(), 4);
+        assert_eq!(path.last(), Some(&goal));
+        assert!(!path.contains(&IVec2::new(1, 1)));
+        let mut previous = start;
+        for tile in path {
+            let step = (tile - previous).abs();
+            assert_eq!(step.x + step.y, 1);
+            previous = tile;
+        }
+    }
+
+    #[test]
+    fn unreachable_goals_have_no_route() {
+        let rows = ["..#.", "..#."];
+        assert_eq!(find_path(&rows, IVec2::new(0, 0), IVec2::new(3, 0)), None);
+        // Nor do walls, or tiles off the map.
+        assert_eq!(find_path(&rows, IVec2::new(0, 0), IVec2::new(2, 0)), None);
+        assert_eq!(find_path(&rows, IVec2::new(0, 0), IVec2::new(9, 0)), None);
+    }
+
+    #[test]
+    fn reachable_tiles_stop_at_walls() {
+        let (tile_map, walls) = maze(&["..#.", "..#."]);
+        let mut reached = tile_map.reachable(IVec2::new(0, 0), |entity| !walls.contains(&entity));
+        reached.sort_by_key(|tile| (tile.x, tile.y));
+        assert_eq!(
+            reached,
+            [
+                IVec2::new(0, 0),
+                IVec2::new(0, 1),
+                IVec2::new(1, 0),
+                IVec2::new(1, 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn routes_to_the_start_are_empty() {
+        let tile = IVec2::new(1, 1);
+        assert_eq!(
+            find_path(&["...", "...", "..."], tile, tile),
+            Some(Vec::new())
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn tile_centers_and_corners_are_on_their_tile(x in -100..100, y in -100..100) {
+            let (tile_map, _) = bar();
+            let tile = IVec2::new(x, y);
+            prop_assert_eq!(tile_map.to_tile(tile_map.tile_center(tile)), tile);
+            prop_assert_eq!(tile_map.to_tile(tile_map.tile_corner(tile)), tile);
+        }
+
+        #[test]
+        fn tiles_round_trip_through_the_world(
+            x in -100..100,
+            y in -100..100,
+            origin_x in -1000..1000,
+            origin_y in -1000..1000,
+        ) {
+            let (tile_map, _) = bar();
+            let origin = Vec3::new(origin_x as f32, origin_y as f32, 0.);
+            let tile = IVec2::new(x, y);
+            let position = tile_map.tile_to_world(origin, tile);
+            prop_assert_eq!(tile_map.world_to_tile(origin, position), tile);
+        }
+
+        #[test]
+        fn points_stay_on_their_tile_through_the_world(
+            x in -3000f32..3000.,
+            y in -3000f32..3000.,
+            z in 0f32..20.,
+        ) {
+            let (tile_map, origin) = bar();
+            let point = Vec3::new(x, y, z);
+            let world = tile_map.tile_space_to_world(origin, point);
+            prop_assert_eq!(world.z, z);
+            prop_assert_eq!(tile_map.to_tile_space(origin, world).z, z);
+            prop_assert_eq!(
+                tile_map.world_to_tile(origin, world),
+                tile_map.to_tile(tile_map.to_tile_space(origin, world))
+            );
+            // Any point is within the tile it is on.
+            let tile = tile_map.to_tile(point);
+            let corner = tile_map.tile_corner(tile).truncate();
+            let extent = tile_map.tile_extent();
+            prop_assert!(point.x >= corner.x && point.x < corner.x + extent.x);
+            prop_assert!(point.y >= corner.y && point.y < corner.y + extent.y);
+        }
+    }
+}
@@ -0,0 +1,227 @@
+use crate::GameState;
+use bevy::prelude::*;
+
+pub struct TweenPlugin;
+
+/// This plugin eases things from where they are to where they're going, so items glide into place
+/// instead of jumping there. Any system can start one by inserting a `Tween`.
+/// Tweens only play during the State `GameState::Playing`, so they hold still while paused.
+impl Plugin for TweenPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(tween.in_set(OnUpdate(GameState::Playing)));
+    }
+}
+
+/// How a tween gets from start to end. Each maps the fraction of the time gone to the fraction
+/// of the way there.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Ease {
+    /// Starts slow and speeds up.
+    QuadIn,
+    /// Starts fast and slows down.
+    QuadOut,
+    /// Overshoots a little and settles back.
+    BackOut,
+}
+
+impl Ease {
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.clamp(0., 1.);
+        match self {
+            Ease::QuadIn => t * t,
+            Ease::QuadOut => 1. - (1. - t) * (1. - t),
+            Ease::BackOut => {
+                let c1 = 1.70158;
+                let c3 = c1 + 1.;
+                1. + c3 * (t - 1.).powi(3) + c1 * (t - 1.).powi(2)
+            }
+        }
+    }
+}
+
+/// Called with the entity once its tween is done.
+type OnComplete = Box<dyn FnOnce(&mut Commands, Entity) + Send + Sync>;
+
+/// Eases an entity's transform and colour to new values over a while, from wherever they are
+/// when it starts. Inserting another replaces it, and its completion callback is never called.
+///
+/// Only x and y of the translation are tweened. Z is left to whatever layers the sprite.
+#[derive(Component)]
+pub struct Tween {
+    duration: f32,
+    elapsed: f32,
+    ease: Ease,
+    translation: Option<Vec2>,
+    scale: Option<Vec3>,
+    rotation: Option<Quat>,
+    color: Option<Color>,
+    /// Where everything was on the first frame.
+    start: Option<Start>,
+    on_complete: Option<OnComplete>,
+}
+
+#[derive(Clone, Copy)]
+struct Start {
+    translation: Vec2,
+    scale: Vec3,
+    rotation: Quat,
+    color: Color,
+}
+
+impl Tween {
+    pub fn new(seconds: f32, ease: Ease) -> Self {
+        Tween {
+            duration: seconds,
+            elapsed: 0.,
+            ease,
+            translation: None,
+            scale: None,
+            rotation: None,
+            color: None,
+            start: None,
+            on_complete: None,
+        }
+    }
+
+    pub fn move_to(mut self, translation: Vec2) -> Self {
+        self.translation = Some(translation);
+        self
+    }
+
+    pub fn scale_to(mut self, scale: Vec3) -> Self {
+        self.scale = Some(scale);
+        self
+    }
+
+    pub fn rotate_to(mut self, rotation: Quat) -> Self {
+        self.rotation = Some(rotation);
+        self
+    }
+
+    /// Tints a sprite, or every section of some text.
+    pub fn fade_to(mut self, color: Color) -> Self {
+        self.color = Some(color);
+        self
+    }
+
+    pub fn on_complete(
+        mut self,
+        on_complete: impl FnOnce(&mut Commands, Entity) + Send + Sync + 'static,
+    ) -> Self {
+        self.on_complete = Some(Box::new(on_complete));
+        self
+    }
+
+    /// Moves on by `delta` seconds, returning how far along the eased values are.
+    fn tick(&mut self, delta: f32) -> f32 {
+        self.elapsed += delta;
+        if self.duration <= 0. {
+            return 1.;
+        }
+        self.ease.apply(self.elapsed / self.duration)
+    }
+
+    fn finished(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+}
+
+/// A tweened entity, with whichever of the things it can fade it has.
+type Tweened = (
+    Entity,
+    &'static mut Tween,
+    &'static mut Transform,
+    Option<&'static mut Sprite>,
+    Option<&'static mut TextureAtlasSprite>,
+    Option<&'static mut Text>,
+);
+
+fn tween(mut commands: Commands, time: Res<Time>, mut query: Query<Tweened>) {
+    for (entity, mut tween, mut transform, mut sprite, mut atlas_sprite, mut text) in &mut query {
+        let start = *tween.start.get_or_insert_with(|| Start {
+            translation: transform.translation.truncate(),
+            scale: transform.scale,
+            rotation: transform.rotation,
+            color: sprite
+                .as_ref()
+                .map(|sprite| sprite.color)
+                .or(atlas_sprite.as_ref().map(|sprite| sprite.color))
+                .or(text
+                    .as_ref()
+                    .and_then(|text| text.sections.first())
+                    .map(|section| section.style.color))
+                .unwrap_or(Color::WHITE),
+        });
+        let t = tween.tick(time.delta_seconds());
+
+        if let Some(translation) = tween.translation {
+            let z = transform.translation.z;
+            transform.translation = start.translation.lerp(translation, t).extend(z);
+        }
+        if let Some(scale) = tween.scale {
+            transform.scale = start.scale.lerp(scale, t);
+        }
+        if let Some(rotation) = tween.rotation {
+            transform.rotation = start.rotation.slerp(rotation, t);
+        }
+        if let Some(color) = tween.color {
+            let color = lerp_color(start.color, color, t);
+            if let Some(sprite) = &mut sprite {
+                sprite.color = color;
+            }
+            if let Some(sprite) = &mut atlas_sprite {
+                sprite.color = color;
+            }
+            if let Some(text) = &mut text {
+                for section in &mut text.sections {
+                    section.style.color = color;
+                }
+            }
+        }
+
+        if tween.finished() {
+            commands.entity(entity).remove::<Tween>();
+            if let Some(on_complete) = tween.on_complete.take() {
+                on_complete(&mut commands, entity);
+            }
+        }
+    }
+}
+
+fn lerp_color(from: Color, to: Color, t: f32) -> Color {
+    let from = Vec4::from(from.as_rgba_f32());
+    let to = Vec4::from(to.as_rgba_f32());
+    Color::from(from.lerp(to, t))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EASES: [Ease; 3] = [Ease::QuadIn, Ease::QuadOut, Ease::BackOut];
+
+    #[test]
+    fn eases_start_and_end_in_place() {
+        for ease in EASES {
+            assert!(ease.apply(0.).abs() < 1e-5, "{:?} starts at 0", ease);
+            assert!((ease.apply(1.) - 1.).abs() < 1e-5, "{:?} ends at 1", ease);
+            assert!((ease.apply(2.) - 1.).abs() < 1e-5, "{:?} stays at 1", ease);
+        }
+        assert!(Ease::QuadIn.apply(0.5) < 0.5);
+        assert!(Ease::QuadOut.apply(0.5) > 0.5);
+        assert!(Ease::BackOut.apply(0.8) > 1.);
+    }
+
+    #[test]
+    fn tweens_finish_after_their_duration() {
+        let mut tween = Tween::new(1., Ease::QuadIn);
+        assert_eq!(tween.tick(0.5), 0.25);
+        assert!(!tween.finished());
+        assert_eq!(tween.tick(1.), 1.);
+        assert!(tween.finished());
+
+        let mut instant = Tween::new(0., Ease::QuadIn);
+        assert_eq!(instant.tick(0.), 1.);
+        assert!(instant.finished());
+    }
+}
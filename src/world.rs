@@ -1,8 +1,10 @@
-use crate::loading::TextureAssets;
-use crate::player::{Container, Interactable, Item, Mixer};
+use crate::level::Level;
+use crate::loading::{LevelAssets, TextureAssets};
 use crate::tilemap::TileMap;
-use crate::GameState;
+use crate::ysort::YSort;
+use crate::{starting_session, GameState, SessionScoped};
 use bevy::prelude::*;
+use serde::Deserialize;
 
 pub const TILE_SIZE: f32 = 32.;
 pub const SCALE: Vec3 = Vec3::new(TILE_SIZE / 16., TILE_SIZE / 16., 1.0);
@@ -14,12 +16,19 @@ pub const WORLD_SIZE: IVec2 = IVec2::new(
 
 pub struct WorldPlugin;
 
-#[derive(Component)]
+/// Spawns the tile map on starting a session. Setup systems that place things on the map run
+/// after it, when the map is there to query.
+#[derive(SystemSet, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct SpawnWorld;
+
+#[derive(Component, Deserialize, Clone, Copy)]
 pub enum Tile {
     Floor,
     Bar,
     BarBack,
     Table,
+    /// Where customers come in and leave.
+    Entrance,
 }
 
 impl Default for Tile {
@@ -35,6 +44,7 @@ impl Tile {
             Tile::Bar => Passable::Blocking,
             Tile::BarBack => Passable::Blocking,
             Tile::Table => Passable::Blocking,
+            Tile::Entrance => Passable::Passable,
         }
     }
 
@@ -44,6 +54,14 @@ impl Tile {
             Tile::Bar => textures.bar.clone(),
             Tile::BarBack => textures.barback.clone(),
             Tile::Table => textures.table.clone(),
+            Tile::Entrance => textures.floor1.clone(),
+        }
+    }
+
+    fn color(&self) -> Color {
+        match self {
+            Tile::Entrance => Color::rgb(0.6, 0.45, 0.3),
+            _ => Color::WHITE,
         }
     }
 }
@@ -58,8 +76,16 @@ pub enum Passable {
 
 impl Plugin for WorldPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system(spawn_world_tiles.in_schedule(OnEnter(GameState::Playing)))
-            .add_system(update_tile_positions.in_set(OnUpdate(GameState::Playing)));
+        app.add_systems(
+            (
+                spawn_world_tiles.run_if(starting_session),
+                apply_system_buffers,
+            )
+                .chain()
+                .in_set(SpawnWorld)
+                .in_schedule(OnEnter(GameState::Playing)),
+        )
+        .add_system(update_tile_positions.in_set(OnUpdate(GameState::Playing)));
     }
 }
 
@@ -72,7 +98,16 @@ fn update_tile_positions(
     tile_map.transform_tiles(&mut transform_query);
 }
 
-fn spawn_world_tiles(mut commands: Commands, textures: Res<TextureAssets>) {
+fn spawn_world_tiles(
+    mut commands: Commands,
+    textures: Res<TextureAssets>,
+    level_assets: Res<LevelAssets>,
+    levels: Res<Assets<Level>>,
+) {
+    let level = levels
+        .get(&level_assets.bar)
+        .expect("Level is loaded before entering Playing");
+
     // Spawn the entity early, so we can add children.
     let tile_map_id = commands
         .spawn(SpatialBundle {
@@ -84,155 +119,27 @@ fn spawn_world_tiles(mut commands: Commands, textures: Res<TextureAssets>) {
             .with_scale(Vec3::new(2., 2., 1.)),
             ..default()
         })
+        .insert(SessionScoped)
         .id();
 
-    let mut tile_map = TileMap::new(WORLD_SIZE, IVec2::new(16, 16), IVec2::new(2, 2));
     // tiles are 0,0 bottom left to ~(25,18) top right
-    //
-    // Layout (B = bar, C = counter, f = floor) (extended out)
-    // P player N npc
-    // 16 fBBBBf
-    // 15 ffffff
-    // 14 ffPfff
-    // 13 ffffff
-    // 12 fCCCCf
-    // 11 ffffff
-    // 10 ffffff
-    // 09 ffffff
-    // 08 fTfTff
-    // 07 ffffff
-    // 06 ffNfff
-    // 05 ffffff
-    // 04 fTfTfT (tables)
-
-    // Bar back, flooring
-    let y = 16;
-    for x in 4..20 {
-        let position = IVec2::new(x, y);
-        let id = spawn_tile(&mut commands, &textures, Tile::BarBack);
-        if x == 12 {
-            let spawner = Interactable::Spawner(Item::Banana).spawn(
-                Vec3::new(0., 16., 0.5),
-                &mut commands,
-                &textures,
-            );
-            commands.entity(id).add_child(spawner);
-        }
-        if x == 10 {
-            let spawner = Interactable::Spawner(Item::Orange).spawn(
-                Vec3::new(0., 16., 0.5),
-                &mut commands,
-                &textures,
-            );
-            commands.entity(id).add_child(spawner);
-        }
-        if x == 14 {
-            let spawner = Interactable::Spawner(Item::Cherry).spawn(
-                Vec3::new(0., 16., 0.5),
-                &mut commands,
-                &textures,
-            );
-            commands.entity(id).add_child(spawner);
-        }
-        if x == 6 {
-            let trash =
-                Interactable::Trash.spawn(Vec3::new(0., 16., 0.5), &mut commands, &textures);
-            commands.entity(id).add_child(trash);
-        }
-        tile_map.insert(tile_map_id, id, position, &mut commands);
-    }
-    for x in 2..4 {
-        let position = IVec2::new(x, y);
-        let id = spawn_floor(&mut commands, &textures);
-        tile_map.insert(tile_map_id, id, position, &mut commands);
-    }
-    for x in 20..22 {
-        let position = IVec2::new(x, y);
-        let id = spawn_floor(&mut commands, &textures);
-        tile_map.insert(tile_map_id, id, position, &mut commands);
-    }
-
-    // Flooring
-    for y in 13..16 {
-        for x in 2..22 {
-            let position = IVec2::new(x, y);
-            let id = spawn_floor(&mut commands, &textures);
-            if y == 16 && x == 12 {
-                let orange = Item::Orange.spawn(Vec3::new(0., 0., 0.5), &mut commands, &textures);
-                commands.entity(id).add_child(orange);
-            } else if y == 17 && x == 22 {
-                let banana = Item::Banana.spawn(Vec3::new(0., 0., 0.5), &mut commands, &textures);
-                commands.entity(id).add_child(banana);
-            }
-            tile_map.insert(tile_map_id, id, position, &mut commands);
-        }
-    }
-
-    // Bar, flooring
-    let y = 12;
-    for x in 4..20 {
-        let position = IVec2::new(x, y);
-        let id = spawn_tile(&mut commands, &textures, Tile::Bar);
-        if x == 6 {
-            let mixer = Interactable::Mixer(Mixer::new()).spawn(
-                Vec3::new(0., 16., 0.5),
-                &mut commands,
-                &textures,
-            );
-            commands.entity(id).add_child(mixer);
-        }
-        if x >= 10 && x <= 16 {
-            let container = Interactable::Container(Container::new()).spawn(
-                Vec3::new(0., 16., 0.5),
-                &mut commands,
-                &textures,
-            );
-            commands.entity(id).add_child(container);
+    let mut tile_map = TileMap::new(WORLD_SIZE, IVec2::new(16, 16), IVec2::new(2, 2));
+    for (position, entry) in level.tiles() {
+        let id = spawn_tile(&mut commands, &textures, entry.tile);
+        if let Some(placement) = &entry.interactable {
+            let interactable =
+                placement
+                    .interactable()
+                    .spawn(Vec3::new(0., 16., 0.), &mut commands, &textures);
+            commands.entity(id).add_child(interactable);
         }
         tile_map.insert(tile_map_id, id, position, &mut commands);
     }
 
-    for x in 2..4 {
-        let position = IVec2::new(x, y);
-        let id = spawn_floor(&mut commands, &textures);
-        tile_map.insert(tile_map_id, id, position, &mut commands);
-    }
-    for x in 20..22 {
-        let position = IVec2::new(x, y);
-        let id = spawn_floor(&mut commands, &textures);
-        tile_map.insert(tile_map_id, id, position, &mut commands);
-    }
-
-    // Floor, tables
-    for y in 04..12 {
-        for x in 2..22 {
-            let position = IVec2::new(x, y);
-            if y == 4 && (x == 4 || x == 8 || x == 12 || x == 16 || x == 20) {
-                tile_map.insert(
-                    tile_map_id,
-                    spawn_tile(&mut commands, &textures, Tile::Table),
-                    position,
-                    &mut commands,
-                );
-            } else {
-                tile_map.insert(
-                    tile_map_id,
-                    spawn_floor(&mut commands, &textures),
-                    position,
-                    &mut commands,
-                );
-            }
-        }
-    }
-
     // Add the tilemap component to the tilemap entity
     commands.entity(tile_map_id).insert(tile_map);
 }
 
-fn spawn_floor(commands: &mut Commands, textures: &Res<TextureAssets>) -> Entity {
-    spawn_tile(commands, textures, Tile::Floor)
-}
-
 // Camera defaults to center of screen being 0.0/0.0
 // So tile 0,0 (top left) will be at -25*16/20*16
 // tile 50,40 will be at 25*16/-20*16
@@ -246,11 +153,13 @@ fn spawn_tile(commands: &mut Commands, textures: &Res<TextureAssets>, tile: Tile
                 transform: Transform::from_translation(translation),
                 sprite: Sprite {
                     anchor: bevy::sprite::Anchor::BottomLeft,
+                    color: tile.color(),
                     ..default()
                 },
                 ..default()
             },
             tile,
+            YSort::TILE,
         ))
         .id()
 }
@@ -0,0 +1,195 @@
+use crate::world::{SCREEN_SIZE, TILE_SIZE};
+use bevy::prelude::*;
+use bevy::transform::TransformSystem;
+
+pub struct YSortPlugin;
+
+/// This plugin layers sprites by how far down the screen they are, so whatever is nearer the
+/// bottom is drawn in front. It sets the z of everything with a `YSort` just before transforms
+/// are propagated, so anything without one keeps its place relative to its parent.
+impl Plugin for YSortPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(
+            y_sort
+                .in_base_set(CoreSet::PostUpdate)
+                .before(TransformSystem::TransformPropagate),
+        );
+    }
+}
+
+/// Sorts a sprite by its world y. Something with a sorted parent, like an item carried by a
+/// bartender or placed on the bar, is layered in front of the parent instead.
+#[derive(Component, Clone, Copy, Default, Debug)]
+pub struct YSort {
+    /// Added to the depth, in tiles. Brings things forward of others at the same y.
+    pub bias: f32,
+}
+
+impl YSort {
+    /// Sorted by the bottom edge.
+    pub const TILE: YSort = YSort { bias: 0. };
+    /// Characters stand in the middle of a tile, half a tile up from its bottom edge. A tile of
+    /// bias puts them in front of the tile they're on, and behind the row below.
+    pub const STANDING: YSort = YSort { bias: 1. };
+    /// Just in front of the parent, and not as far forward as the row below it.
+    pub const ON_TOP: YSort = YSort { bias: 0.25 };
+}
+
+/// Z for a sprite at world `y`, one unit per tile, from zero at the top of the screen.
+pub fn depth(y: f32) -> f32 {
+    (SCREEN_SIZE.y / 2. - y) / TILE_SIZE
+}
+
+fn y_sort(
+    sorted_query: Query<(Entity, &YSort)>,
+    parent_query: Query<&Parent>,
+    mut transform_query: Query<&mut Transform>,
+) {
+    // Parents first, so children are placed in front of where their parent has just been put.
+    let mut sorted: Vec<(usize, Entity, YSort)> = sorted_query
+        .iter()
+        .map(|(entity, y_sort)| (generation(entity, &parent_query), entity, *y_sort))
+        .collect();
+    sorted.sort_by_key(|(generation, _, _)| *generation);
+
+    for (_, entity, y_sort) in sorted {
+        let parent = parent_query.get(entity).ok().map(|parent| parent.get());
+        let parent_transform = parent.map_or(GlobalTransform::IDENTITY, |parent| {
+            world_transform(parent, &parent_query, &transform_query)
+        });
+        let Ok(transform) = transform_query.get(entity) else {
+            continue;
+        };
+        let parent_z = parent_transform.translation().z;
+        let z = if parent.is_some_and(|parent| sorted_query.contains(parent)) {
+            parent_z + y_sort.bias
+        } else {
+            let y = parent_transform.transform_point(transform.translation).y;
+            depth(y) + y_sort.bias
+        };
+
+        let local_z = (z - parent_z) / parent_transform.compute_transform().scale.z;
+        // Only touch the transform when it moves, to leave still sprites unchanged.
+        if transform.translation.z != local_z {
+            let mut transform = transform_query.get_mut(entity).unwrap();
+            transform.translation.z = local_z;
+        }
+    }
+}
+
+/// How many parents up the hierarchy goes.
+fn generation(entity: Entity, parent_query: &Query<&Parent>) -> usize {
+    match parent_query.get(entity) {
+        Ok(parent) => generation(parent.get(), parent_query) + 1,
+        Err(_) => 0,
+    }
+}
+
+/// The transform an entity is about to be given by propagation.
+fn world_transform(
+    entity: Entity,
+    parent_query: &Query<&Parent>,
+    transform_query: &Query<&mut Transform>,
+) -> GlobalTransform {
+    let transform = transform_query.get(entity).copied().unwrap_or_default();
+    match parent_query.get(entity) {
+        Ok(parent) => {
+            world_transform(parent.get(), parent_query, transform_query).mul_transform(transform)
+        }
+        Err(_) => GlobalTransform::from(transform),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::hierarchy::HierarchyPlugin;
+    use bevy::transform::TransformPlugin;
+
+    fn app() -> App {
+        let mut app = App::new();
+        app.add_plugin(HierarchyPlugin)
+            .add_plugin(TransformPlugin)
+            .add_plugin(YSortPlugin);
+        app
+    }
+
+    fn z(app: &App, entity: Entity) -> f32 {
+        app.world
+            .get::<GlobalTransform>(entity)
+            .unwrap()
+            .translation()
+            .z
+    }
+
+    /// A tile of the bar, with a map at the bottom left of the screen drawing it at double size.
+    fn spawn_tile(app: &mut App, tile: IVec2) -> Entity {
+        let map = app
+            .world
+            .spawn(SpatialBundle::from_transform(
+                Transform::from_xyz(-400., -300., 0.).with_scale(Vec3::new(2., 2., 1.)),
+            ))
+            .id();
+        let corner = (tile * 16).as_vec2().extend(0.);
+        let tile = app
+            .world
+            .spawn((
+                SpatialBundle::from_transform(Transform::from_translation(corner)),
+                YSort::TILE,
+            ))
+            .id();
+        app.world.entity_mut(map).add_child(tile);
+        tile
+    }
+
+    fn spawn_standing(app: &mut App, tile: IVec2) -> Entity {
+        let center = Vec3::new(-384. + tile.x as f32 * 32., -284. + tile.y as f32 * 32., 0.);
+        app.world
+            .spawn((
+                SpatialBundle::from_transform(Transform::from_translation(center)),
+                YSort::STANDING,
+            ))
+            .id()
+    }
+
+    #[test]
+    fn characters_are_between_their_tile_and_the_row_below() {
+        let mut app = app();
+        let bar = spawn_tile(&mut app, IVec2::new(3, 4));
+        let floor = spawn_tile(&mut app, IVec2::new(3, 5));
+        let behind = spawn_standing(&mut app, IVec2::new(3, 5));
+        let in_front = spawn_standing(&mut app, IVec2::new(3, 3));
+        app.update();
+
+        assert!(z(&app, floor) < z(&app, behind));
+        assert!(z(&app, behind) < z(&app, bar));
+        assert!(z(&app, bar) < z(&app, in_front));
+    }
+
+    #[test]
+    fn children_are_on_top_of_their_parent() {
+        let mut app = app();
+        let bar = spawn_tile(&mut app, IVec2::new(3, 4));
+        let bartender = spawn_standing(&mut app, IVec2::new(3, 3));
+        let mixer = app
+            .world
+            .spawn((
+                SpatialBundle::from_transform(Transform::from_xyz(0., 16., 0.)),
+                YSort::ON_TOP,
+            ))
+            .id();
+        app.world.entity_mut(bar).add_child(mixer);
+        let emote = app
+            .world
+            .spawn(SpatialBundle::from_transform(Transform::from_xyz(
+                0., 10., 0.1,
+            )))
+            .id();
+        app.world.entity_mut(bartender).add_child(emote);
+        app.update();
+
+        assert_eq!(z(&app, mixer), z(&app, bar) + YSort::ON_TOP.bias);
+        assert!(z(&app, mixer) < z(&app, bartender));
+        assert_eq!(z(&app, emote), z(&app, bartender) + 0.1);
+    }
+}